@@ -0,0 +1,182 @@
+//! Compact keyframe animations that can be stored in flash as `const` data.
+//!
+//! Each [`Keyframe`] stores a 4 bit palette index per voxel, packing a whole frame into 32 bytes.
+//! Keyframes are written as hex strings, one digit per voxel, and converted at compile time:
+//!
+//! ```
+//! use common::animation::Keyframe;
+//!
+//! // Bottom layer lit with colour 1, everything else off
+//! const FRAME: Keyframe = Keyframe::from_hex(
+//!     "1111 1111 1111 1111
+//!      0000 0000 0000 0000
+//!      0000 0000 0000 0000
+//!      0000 0000 0000 0000",
+//!     500,
+//! );
+//! ```
+
+use crate::{palette::Palette, voxel::Voxel};
+
+/// A single frame of an [`Animation`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Keyframe {
+    /// Palette index of each voxel, two voxels per byte with the first in the high nibble.
+    /// Voxels are ordered by X, then Y, then Z, so index `x + y * 4 + z * 16`.
+    pub voxels: [u8; 32],
+
+    /// Time this keyframe is shown for, including any blend into the next keyframe, ms.
+    pub duration: u16,
+}
+
+impl Keyframe {
+    pub const fn new(voxels: [u8; 32], duration: u16) -> Self {
+        Self { voxels, duration }
+    }
+
+    /// Build a keyframe from 64 hex digits, one palette index per voxel. Digits are ordered by X,
+    /// then Y, then Z from the bottom layer up. Whitespace is ignored.
+    ///
+    /// Panics if the string contains anything other than whitespace and exactly 64 hex digits.
+    /// When used to create a `const`, this is a compile error instead.
+    pub const fn from_hex(digits: &str, duration: u16) -> Self {
+        let bytes = digits.as_bytes();
+
+        let mut voxels = [0u8; 32];
+        let mut count = 0;
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let c = bytes[i];
+            i += 1;
+
+            let value = match c {
+                b' ' | b'\n' | b'\r' | b'\t' => continue,
+                b'0'..=b'9' => c - b'0',
+                b'a'..=b'f' => c - b'a' + 10,
+                b'A'..=b'F' => c - b'A' + 10,
+                _ => panic!("Keyframe contains a character that isn't a hex digit"),
+            };
+
+            assert!(count < 64, "Keyframe has more than 64 voxels");
+
+            voxels[count / 2] |= if count % 2 == 0 { value << 4 } else { value };
+            count += 1;
+        }
+
+        assert!(count == 64, "Keyframe has fewer than 64 voxels");
+
+        Self { voxels, duration }
+    }
+
+    /// Palette index of the voxel at a frame index.
+    pub fn index_at(&self, idx: usize) -> u8 {
+        let Voxel { x, y, z } = Voxel::from_index_unchecked(idx);
+
+        let position = x as usize + y as usize * 4 + z as usize * 16;
+
+        let byte = self.voxels[position / 2];
+
+        // High nibble holds the even voxel
+        match position % 2 {
+            0 => byte >> 4,
+            _ => byte & 0x0f,
+        }
+    }
+}
+
+/// A sequence of keyframes and the palette their voxels index into.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Animation {
+    /// Colours referenced by each keyframe. Only the first 16 colours can be used.
+    pub palette: Palette,
+
+    pub keyframes: &'static [Keyframe],
+}
+
+impl Animation {
+    /// Create an animation. Panics if `keyframes` is empty.
+    pub const fn new(palette: Palette, keyframes: &'static [Keyframe]) -> Self {
+        assert!(
+            !keyframes.is_empty(),
+            "Animation must contain at least one keyframe"
+        );
+
+        Self { palette, keyframes }
+    }
+
+    /// Total time to play every keyframe once, ms.
+    pub fn duration(&self) -> u32 {
+        self.keyframes
+            .iter()
+            .map(|keyframe| keyframe.duration as u32)
+            .sum()
+    }
+
+    /// Index of the keyframe shown at `time` ms into the animation and how far through it
+    /// playback is, from `0.0` to `1.0`. Times past the end of the animation hold the last
+    /// keyframe.
+    pub fn keyframe_at(&self, time: u32) -> (usize, f32) {
+        let mut start = 0;
+
+        for (idx, keyframe) in self.keyframes.iter().enumerate() {
+            let duration = keyframe.duration as u32;
+
+            if time < start + duration {
+                return (idx, (time - start) as f32 / duration as f32);
+            }
+
+            start += duration;
+        }
+
+        (self.keyframes.len() - 1, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apa106led::Apa106Led;
+
+    const FRAME: Keyframe = Keyframe::from_hex(
+        "1000 0000 0000 000f
+         0000 0000 0000 0000
+         0000 0000 0000 0000
+         0000 0000 0000 00A2",
+        100,
+    );
+
+    #[test]
+    fn hex_keyframe() {
+        assert_eq!(FRAME.voxels[0], 0x10);
+        assert_eq!(FRAME.voxels[7], 0x0f);
+        assert_eq!(FRAME.voxels[31], 0xa2);
+
+        let at = |x, y, z| FRAME.index_at(Voxel { x, y, z }.into_index_unchecked());
+
+        assert_eq!(at(0, 0, 0), 1);
+        assert_eq!(at(3, 3, 0), 15);
+        assert_eq!(at(2, 3, 3), 10);
+        assert_eq!(at(3, 3, 3), 2);
+        assert_eq!(at(1, 0, 0), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn short_keyframe() {
+        Keyframe::from_hex("0123", 100);
+    }
+
+    #[test]
+    fn keyframe_timing() {
+        const KEYFRAMES: [Keyframe; 2] = [Keyframe::new([0; 32], 100), Keyframe::new([0; 32], 300)];
+
+        let animation = Animation::new(Palette::new(&[Apa106Led::OFF]), &KEYFRAMES);
+
+        assert_eq!(animation.duration(), 400);
+        assert_eq!(animation.keyframe_at(0), (0, 0.0));
+        assert_eq!(animation.keyframe_at(50), (0, 0.5));
+        assert_eq!(animation.keyframe_at(250), (1, 0.5));
+        assert_eq!(animation.keyframe_at(1000), (1, 1.0));
+    }
+}
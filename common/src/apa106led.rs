@@ -1,3 +1,6 @@
+#[cfg(not(test))]
+use micromath::F32Ext;
+
 pub const ON_BYTE: u8 = 0b1111_1100;
 pub const OFF_BYTE: u8 = 0b1100_0000;
 
@@ -20,6 +23,22 @@ pub struct Apa106Led {
     pub blue: u8,
 }
 
+/// CIE 1931 lightness curve. Maps a perceived brightness level to a linear output multiplier, where
+/// 255 is full brightness.
+const BRIGHTNESS_CURVE: [u8; 256] = [
+    0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 3, 3, 3, 3, 3, 3, 3, 4,
+    4, 4, 4, 4, 4, 5, 5, 5, 5, 5, 6, 6, 6, 6, 6, 7, 7, 7, 7, 8, 8, 8, 8, 9, 9, 9, 10, 10, 10, 10,
+    11, 11, 11, 12, 12, 12, 13, 13, 13, 14, 14, 15, 15, 15, 16, 16, 17, 17, 17, 18, 18, 19, 19, 20,
+    20, 21, 21, 22, 22, 23, 23, 24, 24, 25, 25, 26, 26, 27, 28, 28, 29, 29, 30, 31, 31, 32, 32, 33,
+    34, 34, 35, 36, 37, 37, 38, 39, 39, 40, 41, 42, 43, 43, 44, 45, 46, 47, 47, 48, 49, 50, 51, 52,
+    53, 54, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63, 64, 65, 66, 67, 68, 70, 71, 72, 73, 74, 75, 76,
+    77, 79, 80, 81, 82, 83, 85, 86, 87, 88, 90, 91, 92, 94, 95, 96, 98, 99, 100, 102, 103, 105,
+    106, 108, 109, 110, 112, 113, 115, 116, 118, 120, 121, 123, 124, 126, 128, 129, 131, 132, 134,
+    136, 138, 139, 141, 143, 145, 146, 148, 150, 152, 154, 155, 157, 159, 161, 163, 165, 167, 169,
+    171, 173, 175, 177, 179, 181, 183, 185, 187, 189, 191, 193, 196, 198, 200, 202, 204, 207, 209,
+    211, 214, 216, 218, 220, 223, 225, 228, 230, 232, 235, 237, 240, 242, 245, 247, 250, 252, 255,
+];
+
 fn lerp(a: f32, b: f32, c: f32) -> f32 {
     (1.0 - c) * a + c * b
 }
@@ -60,16 +79,51 @@ impl Apa106Led {
         }
     }
 
-    pub fn divide_by(&self, divisor: u8) -> Self {
+    /// Create a colour from a hue, saturation and value.
+    ///
+    /// `hue` runs from `0.0` (red) through green and blue back to red at `1.0`, wrapping outside
+    /// this range. `saturation` and `value` are clamped to `0.0..=1.0`.
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let value = value.clamp(0.0, 1.0) * 255.0;
+
+        let hue = (hue - hue.floor()) * 6.0;
+        let sector = hue as u8;
+        let f = hue - sector as f32;
+
+        let p = (value * (1.0 - saturation)) as u8;
+        let q = (value * (1.0 - saturation * f)) as u8;
+        let t = (value * (1.0 - saturation * (1.0 - f))) as u8;
+        let v = value as u8;
+
+        let (red, green, blue) = match sector {
+            0 => (v, t, p),
+            1 => (q, v, p),
+            2 => (p, v, t),
+            3 => (p, q, v),
+            4 => (t, p, v),
+            _ => (v, p, q),
+        };
+
+        Self { red, green, blue }
+    }
+
+    /// Scale this colour by a global brightness level from `0` (off) to `255` (full brightness).
+    ///
+    /// The level is mapped through a perceptual curve so that equal steps in `brightness` look
+    /// like equal steps in perceived brightness.
+    pub fn dim(self, brightness: u8) -> Self {
+        let multiplier = BRIGHTNESS_CURVE[brightness as usize] as u16;
+
         Self {
-            red: self.red / divisor,
-            green: self.green / divisor,
-            blue: self.blue / divisor,
+            red: (self.red as u16 * multiplier / 255) as u8,
+            green: (self.green as u16 * multiplier / 255) as u8,
+            blue: (self.blue as u16 * multiplier / 255) as u8,
         }
     }
 
     pub fn as_bitbang_data(&self) -> [u8; 24] {
-        colour_to_raw(&self)
+        colour_to_raw(self)
     }
 }
 
@@ -200,6 +254,63 @@ mod tests {
         );
     }
 
+    #[test]
+    fn hsv() {
+        assert_eq!(
+            Apa106Led::from_hsv(0.0, 1.0, 1.0),
+            Apa106Led {
+                red: 255,
+                green: 0,
+                blue: 0
+            }
+        );
+        assert_eq!(
+            Apa106Led::from_hsv(2.0 / 3.0, 1.0, 1.0),
+            Apa106Led {
+                red: 0,
+                green: 0,
+                blue: 255
+            }
+        );
+        assert_eq!(
+            Apa106Led::from_hsv(-1.0 / 3.0, 1.0, 0.5),
+            Apa106Led::from_hsv(2.0 / 3.0, 1.0, 0.5)
+        );
+        assert_eq!(Apa106Led::from_hsv(0.3, 0.0, 1.0).red, 255);
+        assert_eq!(Apa106Led::from_hsv(0.3, 1.0, 0.0), OFF);
+    }
+
+    #[test]
+    fn dim_extremes() {
+        let colour = Apa106Led {
+            red: 255,
+            green: 183,
+            blue: 76,
+        };
+
+        assert_eq!(colour.dim(255), colour);
+        assert_eq!(colour.dim(0), OFF);
+    }
+
+    #[test]
+    fn dim_is_perceptual() {
+        let white = Apa106Led {
+            red: 255,
+            green: 255,
+            blue: 255,
+        };
+
+        // Half perceived brightness is much less than half the linear output
+        assert_eq!(
+            white.dim(127),
+            Apa106Led {
+                red: 47,
+                green: 47,
+                blue: 47
+            }
+        );
+    }
+
     #[test]
     fn lerping() {
         let white = Apa106Led {
@@ -0,0 +1,280 @@
+use crate::{
+    apa106led::Apa106Led,
+    voxel::{Voxel, VoxelError},
+};
+
+/// Size in bytes of a serialised [`Calibration`] table.
+pub const CALIBRATION_LEN: usize = 64 * 4;
+
+/// Bit in the flags byte of a serialised LED entry marking the LED as disabled.
+const FLAG_DISABLED: u8 = 0b0000_0001;
+
+/// Correction for a single LED.
+///
+/// Gains are multipliers from `0` (channel off) to `255` (channel unchanged), so brighter LEDs are
+/// matched to dimmer ones by turning them down.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LedCalibration {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+
+    /// Dead or misbehaving LED. Always output as off.
+    pub disabled: bool,
+}
+
+impl LedCalibration {
+    pub const IDENTITY: LedCalibration = LedCalibration {
+        red: 255,
+        green: 255,
+        blue: 255,
+        disabled: false,
+    };
+
+    pub fn apply(&self, colour: Apa106Led) -> Apa106Led {
+        if self.disabled {
+            return Apa106Led::OFF;
+        }
+
+        Apa106Led {
+            red: scale(colour.red, self.red),
+            green: scale(colour.green, self.green),
+            blue: scale(colour.blue, self.blue),
+        }
+    }
+}
+
+impl Default for LedCalibration {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// Per-LED calibration table, stored in frame index order.
+///
+/// The table serialises to [`CALIBRATION_LEN`] bytes: 4 bytes per LED made up of the red, green
+/// and blue gains followed by a flags byte. This allows a table to be generated on a host with
+/// [`Calibration::to_bytes`] and baked into the firmware with
+///
+/// ```rust,ignore
+/// const CALIBRATION: Calibration = Calibration::from_bytes(include_bytes!("calibration.bin"));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Calibration {
+    leds: [LedCalibration; 64],
+}
+
+impl Calibration {
+    /// Calibration table that leaves every LED unchanged.
+    pub const IDENTITY: Calibration = Calibration {
+        leds: [LedCalibration::IDENTITY; 64],
+    };
+
+    /// Get the calibration at a frame index, or an error if `index` is larger than 63.
+    pub fn try_get_at_index(&self, index: usize) -> Result<LedCalibration, VoxelError> {
+        self.leds
+            .get(index)
+            .copied()
+            .ok_or(VoxelError::IndexOutOfRange(index))
+    }
+
+    /// Get the calibration at a frame index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is larger than 63. Use [`Calibration::try_get_at_index`] for untrusted
+    /// input.
+    pub fn get_at_index_unchecked(&self, index: usize) -> LedCalibration {
+        self.leds[index]
+    }
+
+    /// Set the calibration at a frame index, or return an error if `index` is larger than 63.
+    pub fn try_set_at_index(
+        &mut self,
+        index: usize,
+        calibration: LedCalibration,
+    ) -> Result<(), VoxelError> {
+        let led = self
+            .leds
+            .get_mut(index)
+            .ok_or(VoxelError::IndexOutOfRange(index))?;
+
+        *led = calibration;
+
+        Ok(())
+    }
+
+    /// Set the calibration at a frame index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is larger than 63. Use [`Calibration::try_set_at_index`] for untrusted
+    /// input.
+    pub fn set_at_index_unchecked(&mut self, index: usize, calibration: LedCalibration) {
+        self.leds[index] = calibration;
+    }
+
+    /// Get the calibration for a voxel, or an error if `coord` lies outside the cube.
+    pub fn try_get_at_coord(&self, coord: Voxel) -> Result<LedCalibration, VoxelError> {
+        Ok(self.leds[coord.try_into_index()?])
+    }
+
+    /// Set the calibration for a voxel, or return an error if `coord` lies outside the cube.
+    pub fn try_set_at_coord(
+        &mut self,
+        coord: Voxel,
+        calibration: LedCalibration,
+    ) -> Result<(), VoxelError> {
+        self.leds[coord.try_into_index()?] = calibration;
+
+        Ok(())
+    }
+
+    /// Mask out a dead LED so it is never lit, or return an error if `coord` lies outside the cube.
+    pub fn try_disable(&mut self, coord: Voxel) -> Result<(), VoxelError> {
+        self.leds[coord.try_into_index()?].disabled = true;
+
+        Ok(())
+    }
+
+    /// Correct a colour for the LED at the given frame index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is larger than 63.
+    pub fn apply_unchecked(&self, index: usize, colour: Apa106Led) -> Apa106Led {
+        self.leds[index].apply(colour)
+    }
+
+    pub const fn from_bytes(bytes: &[u8; CALIBRATION_LEN]) -> Self {
+        let mut leds = [LedCalibration::IDENTITY; 64];
+
+        let mut idx = 0;
+
+        while idx < 64 {
+            let start = idx * 4;
+
+            leds[idx] = LedCalibration {
+                red: bytes[start],
+                green: bytes[start + 1],
+                blue: bytes[start + 2],
+                disabled: bytes[start + 3] & FLAG_DISABLED != 0,
+            };
+
+            idx += 1;
+        }
+
+        Self { leds }
+    }
+
+    pub fn to_bytes(&self) -> [u8; CALIBRATION_LEN] {
+        let mut bytes = [0u8; CALIBRATION_LEN];
+
+        for (chunk, led) in bytes.chunks_exact_mut(4).zip(self.leds.iter()) {
+            chunk[0] = led.red;
+            chunk[1] = led.green;
+            chunk[2] = led.blue;
+            chunk[3] = if led.disabled { FLAG_DISABLED } else { 0 };
+        }
+
+        bytes
+    }
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+fn scale(value: u8, gain: u8) -> u8 {
+    (value as u16 * gain as u16 / 255) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity() {
+        let colour = Apa106Led::WARM_WHITE;
+
+        for idx in 0..64 {
+            assert_eq!(Calibration::IDENTITY.apply_unchecked(idx, colour), colour);
+        }
+    }
+
+    #[test]
+    fn gain_and_disable() {
+        let mut calibration = Calibration::default();
+
+        calibration
+            .try_set_at_coord(
+                Voxel { x: 1, y: 2, z: 3 },
+                LedCalibration {
+                    red: 255,
+                    green: 127,
+                    blue: 0,
+                    disabled: false,
+                },
+            )
+            .unwrap();
+        calibration.try_disable(Voxel { x: 0, y: 0, z: 0 }).unwrap();
+
+        let white = Apa106Led {
+            red: 255,
+            green: 255,
+            blue: 255,
+        };
+
+        assert_eq!(
+            calibration.apply_unchecked(Voxel { x: 1, y: 2, z: 3 }.into_index_unchecked(), white),
+            Apa106Led {
+                red: 255,
+                green: 127,
+                blue: 0
+            }
+        );
+        assert_eq!(calibration.apply_unchecked(0, white), Apa106Led::OFF);
+        assert_eq!(calibration.apply_unchecked(1, white), white);
+    }
+
+    #[test]
+    fn round_trip_bytes() {
+        let mut calibration = Calibration::default();
+
+        calibration.set_at_index_unchecked(
+            10,
+            LedCalibration {
+                red: 200,
+                green: 180,
+                blue: 255,
+                disabled: false,
+            },
+        );
+        calibration.try_disable(Voxel { x: 3, y: 3, z: 3 }).unwrap();
+
+        let bytes = calibration.to_bytes();
+
+        assert_eq!(&bytes[40..44], &[200, 180, 255, 0]);
+        assert_eq!(Calibration::from_bytes(&bytes), calibration);
+    }
+
+    #[test]
+    fn index_out_of_range() {
+        let mut calibration = Calibration::default();
+
+        assert_eq!(
+            calibration.try_set_at_index(64, LedCalibration::IDENTITY),
+            Err(VoxelError::IndexOutOfRange(64))
+        );
+        assert_eq!(
+            calibration.try_get_at_index(63),
+            Ok(LedCalibration::IDENTITY)
+        );
+        assert_eq!(
+            calibration.try_get_at_coord(Voxel { x: 4, y: 0, z: 0 }),
+            Err(VoxelError::CoordOutOfRange { x: 4, y: 0, z: 0 })
+        );
+    }
+}
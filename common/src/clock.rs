@@ -0,0 +1,271 @@
+//! Pattern time, decoupled from wall time so playback can be slowed down, sped up, reversed or
+//! paused.
+
+use core::ops::Range;
+
+/// Fastest playback speed in either direction, as a multiple of real time.
+pub const MAX_TIME_SCALE: f32 = 1024.0;
+
+/// Converts wall time into pattern time.
+///
+/// Each [`tick`](Clock::tick) advances pattern time by the wall time elapsed since the last tick,
+/// multiplied by the time scale. Pattern time never runs below a given floor, so reversing stops at
+/// the start of the current pattern.
+///
+/// Patterns that are a pure function of time play backwards when reversed. Patterns that simulate
+/// state from frame to frame hold their current frame instead.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Clock {
+    /// Current pattern time, ms.
+    time: u32,
+
+    /// Fraction of a millisecond carried over to the next tick, so slow playback doesn't stall.
+    remainder: f32,
+
+    /// Wall time of the last tick, ms.
+    last_tick: u32,
+
+    /// Multiplier from wall time to pattern time. Negative values play backwards.
+    time_scale: f32,
+
+    paused: bool,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self {
+            time: 0,
+            remainder: 0.0,
+            last_tick: 0,
+            time_scale: 1.0,
+            paused: false,
+        }
+    }
+}
+
+impl Clock {
+    /// Current pattern time, ms.
+    pub fn time(&self) -> u32 {
+        self.time
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// Set how fast pattern time runs compared to wall time. `1.0` is normal speed, `0.5` is half
+    /// speed, and negative values play backwards.
+    ///
+    /// The scale is clamped to [`MAX_TIME_SCALE`] in either direction. NaN is ignored.
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        if !time_scale.is_nan() {
+            self.time_scale = time_scale.clamp(-MAX_TIME_SCALE, MAX_TIME_SCALE);
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Stop pattern time from advancing. The current frame keeps being shown.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Continue from where playback was paused.
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Advance pattern time by a single frame of `frame_time` ms of wall time, scaled by the time
+    /// scale. Mostly useful while paused.
+    pub fn step(&mut self, frame_time: u32, floor: u32) {
+        self.advance(frame_time as f32 * self.time_scale, floor);
+    }
+
+    /// Advance pattern time by the wall time since the last tick, returning the new pattern time.
+    /// Pattern time won't go below `floor`.
+    pub fn tick(&mut self, wall_time: u32, floor: u32) -> u32 {
+        let elapsed = wall_time.saturating_sub(self.last_tick);
+
+        self.last_tick = wall_time;
+
+        if !self.paused {
+            self.advance(elapsed as f32 * self.time_scale, floor);
+        }
+
+        self.time
+    }
+
+    fn advance(&mut self, delta: f32, floor: u32) {
+        let delta = delta + self.remainder;
+        let whole = delta as i64;
+
+        self.remainder = delta - whole as f32;
+
+        self.time = (self.time as i64)
+            .saturating_add(whole)
+            .clamp(floor as i64, u32::MAX as i64) as u32;
+
+        // Don't build up time to run backwards through while stuck at the floor
+        if self.time == floor && self.remainder < 0.0 {
+            self.remainder = 0.0;
+        }
+    }
+}
+
+/// Runs a simulation in fixed time steps, so it produces the same frames however often it's
+/// updated.
+///
+/// Jumping back in time holds the current state until time catches up again. Jumping a long way
+/// forward runs at most `max_steps` steps to catch up instead of stalling the frame.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FixedStep {
+    /// Time between steps, ms.
+    step_time: u32,
+
+    /// Largest number of steps run by a single update.
+    max_steps: u32,
+
+    /// Time the next step is due, ms.
+    next_step: u32,
+}
+
+impl FixedStep {
+    /// Create a stepper with a step every `step_time` ms, running the first step at time 0.
+    pub const fn new(step_time: u32, max_steps: u32) -> Self {
+        Self {
+            step_time: if step_time > 0 { step_time } else { 1 },
+            max_steps,
+            next_step: 0,
+        }
+    }
+
+    /// Time between steps, ms.
+    pub fn step_time(&self) -> u32 {
+        self.step_time
+    }
+
+    pub fn set_step_time(&mut self, step_time: u32) {
+        self.step_time = step_time.max(1);
+    }
+
+    /// Steps due by `time`, to be run in turn. For example:
+    ///
+    /// ```rust,ignore
+    /// for _ in self.fixed_step.steps(time) {
+    ///     self.step();
+    /// }
+    /// ```
+    pub fn steps(&mut self, time: u32) -> Range<u32> {
+        // Jumped back in time. Hold the current state until time catches up.
+        if time.saturating_add(self.step_time) < self.next_step {
+            self.next_step = time.saturating_add(self.step_time);
+        }
+
+        let catch_up = self.step_time.saturating_mul(self.max_steps);
+
+        if time > self.next_step.saturating_add(catch_up) {
+            self.next_step = time - catch_up;
+        }
+
+        if time < self.next_step {
+            return 0..0;
+        }
+
+        let steps = (time - self.next_step) / self.step_time + 1;
+
+        self.next_step = self
+            .next_step
+            .saturating_add(steps.saturating_mul(self.step_time));
+
+        0..steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaled_time() {
+        let mut clock = Clock::default();
+
+        assert_eq!(clock.tick(100, 0), 100);
+
+        clock.set_time_scale(0.25);
+
+        // Fractions of a millisecond carry over
+        assert_eq!(clock.tick(102, 0), 100);
+        assert_eq!(clock.tick(104, 0), 101);
+
+        clock.set_time_scale(-2.0);
+
+        assert_eq!(clock.tick(124, 0), 61);
+        assert_eq!(clock.tick(200, 50), 50);
+    }
+
+    #[test]
+    fn extreme_time_scales() {
+        let mut clock = Clock::default();
+
+        clock.set_time_scale(f32::INFINITY);
+
+        assert_eq!(clock.time_scale(), MAX_TIME_SCALE);
+
+        clock.set_time_scale(f32::NAN);
+
+        assert_eq!(clock.time_scale(), MAX_TIME_SCALE);
+        assert_eq!(clock.tick(u32::MAX, 0), u32::MAX);
+
+        clock.set_time_scale(f32::NEG_INFINITY);
+
+        assert_eq!(clock.time_scale(), -MAX_TIME_SCALE);
+
+        clock.step(u32::MAX, 10);
+
+        assert_eq!(clock.time(), 10);
+
+        // Still runs normally afterwards
+        clock.set_time_scale(1.0);
+        clock.step(5, 0);
+
+        assert_eq!(clock.time(), 15);
+    }
+
+    #[test]
+    fn pause_and_step() {
+        let mut clock = Clock::default();
+
+        clock.tick(100, 0);
+        clock.pause();
+
+        assert_eq!(clock.tick(500, 0), 100);
+
+        clock.step(33, 0);
+
+        assert_eq!(clock.time(), 133);
+
+        clock.resume();
+
+        // Time spent paused is skipped
+        assert_eq!(clock.tick(510, 0), 143);
+    }
+
+    #[test]
+    fn fixed_steps() {
+        let mut fixed_step = FixedStep::new(10, 4);
+
+        assert_eq!(fixed_step.steps(0).len(), 1);
+        assert_eq!(fixed_step.steps(5).len(), 0);
+        assert_eq!(fixed_step.steps(25).len(), 2);
+
+        // Back in time holds until time catches up again
+        assert_eq!(fixed_step.steps(0).len(), 0);
+        assert_eq!(fixed_step.steps(10).len(), 1);
+
+        // Large jumps forward only catch up by a limited number of steps
+        assert_eq!(fixed_step.steps(10_000).len(), 5);
+        assert_eq!(fixed_step.steps(u32::MAX).len(), 5);
+    }
+}
@@ -1,15 +1,23 @@
-use crate::{apa106led::Apa106Led, voxel::Voxel};
+use crate::{
+    apa106led::Apa106Led,
+    calibration::Calibration,
+    voxel::{Voxel, VoxelError},
+};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Cube {
     frame: [Apa106Led; 64],
 
-    /// 1 / [divider] brightness scaling
-    pub brightness_divider: u8,
+    /// Global brightness from `0` (off) to `255` (full brightness), applied when the frame is
+    /// output to the LEDs.
+    pub brightness: u8,
+
+    /// Per-LED colour correction and dead pixel mask, applied when the frame is output.
+    pub calibration: Calibration,
 }
 
 impl Cube {
-    pub fn new(brightness_divider: u8) -> Cube {
+    pub fn new(brightness: u8) -> Cube {
         let blank_frame: [Apa106Led; 64] = [Apa106Led {
             red: 1,
             green: 0,
@@ -18,7 +26,8 @@ impl Cube {
 
         Cube {
             frame: blank_frame,
-            brightness_divider,
+            brightness,
+            calibration: Calibration::IDENTITY,
         }
     }
 
@@ -30,18 +39,79 @@ impl Cube {
         &mut self.frame
     }
 
-    pub fn set_at_index(&mut self, index: usize, colour: Apa106Led) {
+    /// Iterate over the frame with calibration and global brightness applied, ready to be sent to
+    /// the LEDs.
+    pub fn output_iter(&self) -> impl Iterator<Item = Apa106Led> + '_ {
+        let brightness = self.brightness;
+
+        self.frame.iter().enumerate().map(move |(idx, colour)| {
+            self.calibration
+                .apply_unchecked(idx, *colour)
+                .dim(brightness)
+        })
+    }
+
+    /// Set the colour at a frame index, or return an error if `index` is larger than 63.
+    pub fn try_set_at_index(&mut self, index: usize, colour: Apa106Led) -> Result<(), VoxelError> {
+        let led = self
+            .frame
+            .get_mut(index)
+            .ok_or(VoxelError::IndexOutOfRange(index))?;
+
+        *led = colour;
+
+        Ok(())
+    }
+
+    /// Set the colour at a frame index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is larger than 63. Use [`Cube::try_set_at_index`] for untrusted input.
+    pub fn set_at_index_unchecked(&mut self, index: usize, colour: Apa106Led) {
         self.frame[index] = colour;
     }
 
-    pub fn set_at_coord(&mut self, coord: Voxel, colour: Apa106Led) {
-        let idx = coord.into_index();
+    /// Set a voxel's colour, or return an error if `coord` lies outside the cube.
+    pub fn try_set_at_coord(&mut self, coord: Voxel, colour: Apa106Led) -> Result<(), VoxelError> {
+        let idx = coord.try_into_index()?;
+
+        self.frame[idx] = colour;
+
+        Ok(())
+    }
+
+    /// Set a voxel's colour.
+    ///
+    /// # Panics
+    ///
+    /// May panic or set the wrong voxel if `coord` lies outside the cube. Use
+    /// [`Cube::try_set_at_coord`] for untrusted input.
+    pub fn set_at_coord_unchecked(&mut self, coord: Voxel, colour: Apa106Led) {
+        debug_assert!(coord.is_in_bounds(), "{:?} out of range", coord);
+
+        let idx = coord.into_index_unchecked();
 
         self.frame[idx] = colour;
     }
 
-    pub fn get_at_coord(&self, coord: Voxel) -> Apa106Led {
-        let idx = coord.into_index();
+    /// Get a voxel's colour, or an error if `coord` lies outside the cube.
+    pub fn try_get_at_coord(&self, coord: Voxel) -> Result<Apa106Led, VoxelError> {
+        let idx = coord.try_into_index()?;
+
+        Ok(self.frame[idx])
+    }
+
+    /// Get a voxel's colour.
+    ///
+    /// # Panics
+    ///
+    /// May panic or return the wrong voxel if `coord` lies outside the cube. Use
+    /// [`Cube::try_get_at_coord`] for untrusted input.
+    pub fn get_at_coord_unchecked(&self, coord: Voxel) -> Apa106Led {
+        debug_assert!(coord.is_in_bounds(), "{:?} out of range", coord);
+
+        let idx = coord.into_index_unchecked();
 
         self.frame[idx]
     }
@@ -50,33 +120,51 @@ impl Cube {
         self.frame = [fill_colour; 64];
     }
 
-    pub fn fill_layer(&mut self, layer: u8, fill_colour: Apa106Led) {
+    /// Fill a horizontal layer at height `layer`.
+    pub fn fill_layer(&mut self, layer: u8, fill_colour: Apa106Led) -> Result<(), VoxelError> {
+        Voxel::new(0, 0, layer)?;
+
         for x in 0..4 {
             for y in 0..4 {
-                self.set_at_coord(Voxel { x, y, z: layer }, fill_colour);
+                self.set_at_coord_unchecked(Voxel { x, y, z: layer }, fill_colour);
             }
         }
+
+        Ok(())
     }
 
-    pub fn fill_slice(&mut self, slice: u8, fill_colour: Apa106Led) {
+    /// Fill a vertical slice at X position `slice`.
+    pub fn fill_slice(&mut self, slice: u8, fill_colour: Apa106Led) -> Result<(), VoxelError> {
+        Voxel::new(slice, 0, 0)?;
+
         for y in 0..4 {
             for z in 0..4 {
-                self.set_at_coord(Voxel { x: slice, y, z }, fill_colour);
+                self.set_at_coord_unchecked(Voxel { x: slice, y, z }, fill_colour);
             }
         }
+
+        Ok(())
     }
 
-    pub fn fill_panel(&mut self, panel: u8, fill_colour: Apa106Led) {
+    /// Fill a vertical panel at Y position `panel`.
+    pub fn fill_panel(&mut self, panel: u8, fill_colour: Apa106Led) -> Result<(), VoxelError> {
+        Voxel::new(0, panel, 0)?;
+
         for x in 0..4 {
             for z in 0..4 {
-                self.set_at_coord(Voxel { x, y: panel, z }, fill_colour);
+                self.set_at_coord_unchecked(Voxel { x, y: panel, z }, fill_colour);
             }
         }
+
+        Ok(())
     }
 
-    pub fn fill_column(&mut self, column: Voxel, fill_colour: Apa106Led) {
+    /// Fill the vertical column containing `column`. The Z coordinate is ignored.
+    pub fn fill_column(&mut self, column: Voxel, fill_colour: Apa106Led) -> Result<(), VoxelError> {
+        Voxel::new(column.x, column.y, 0)?;
+
         for z in 0..4 {
-            self.set_at_coord(
+            self.set_at_coord_unchecked(
                 Voxel {
                     x: column.x,
                     y: column.y,
@@ -85,6 +173,8 @@ impl Cube {
                 fill_colour,
             );
         }
+
+        Ok(())
     }
 
     /// Fill by index with a pixel iterator.
@@ -93,7 +183,7 @@ impl Cube {
     /// ignored. Shorter iterators will not fail, but will leave the cube in a broken state.
     pub fn fill_iter(&mut self, iter: impl IntoIterator<Item = Apa106Led>) {
         for (idx, colour) in iter.into_iter().take(64).enumerate() {
-            self.set_at_index(idx, colour)
+            self.set_at_index_unchecked(idx, colour)
         }
     }
 }
@@ -0,0 +1,22 @@
+//! Input from buttons, rotary encoders and analog axes, delivered to the active pattern through
+//! [`State::input`](crate::State::input).
+//!
+//! Controls are identified by index so the same patterns work with whatever hardware is attached.
+//! By convention, button 0 is the main button, and axes 0 and 1 are X and Y.
+
+/// A single input from the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum InputEvent {
+    /// Button with the given index was pressed.
+    ButtonDown(u8),
+
+    /// Button with the given index was released.
+    ButtonUp(u8),
+
+    /// Rotary encoder turned by a number of detents. Positive steps are clockwise.
+    Encoder { encoder: u8, steps: i8 },
+
+    /// New position of an analog axis such as a joystick or tilt sensor, from `-1.0` to `1.0`
+    /// where `0.0` is centred.
+    Axis { axis: u8, value: f32 },
+}
@@ -1,10 +1,19 @@
 #![no_std]
 
+pub mod animation;
 pub mod apa106led;
+pub mod calibration;
+pub mod clock;
 pub mod cube;
+pub mod input;
+pub mod noise;
+pub mod palette;
+pub mod particles;
 pub mod patterns;
 pub mod state;
 pub mod transitions;
+pub mod vec3;
+pub mod vm;
 pub mod voxel;
 
 pub use state::State;
@@ -0,0 +1,154 @@
+//! 4D gradient (Perlin) noise, for sampling a 3D field that changes smoothly over time.
+
+#[cfg(not(test))]
+use micromath::F32Ext;
+
+/// Ken Perlin's reference permutation table.
+const PERMUTATION: [u8; 256] = [
+    151, 160, 137, 91, 90, 15, 131, 13, 201, 95, 96, 53, 194, 233, 7, 225, 140, 36, 103, 30, 69,
+    142, 8, 99, 37, 240, 21, 10, 23, 190, 6, 148, 247, 120, 234, 75, 0, 26, 197, 62, 94, 252, 219,
+    203, 117, 35, 11, 32, 57, 177, 33, 88, 237, 149, 56, 87, 174, 20, 125, 136, 171, 168, 68, 175,
+    74, 165, 71, 134, 139, 48, 27, 166, 77, 146, 158, 231, 83, 111, 229, 122, 60, 211, 133, 230,
+    220, 105, 92, 41, 55, 46, 245, 40, 244, 102, 143, 54, 65, 25, 63, 161, 1, 216, 80, 73, 209, 76,
+    132, 187, 208, 89, 18, 169, 200, 196, 135, 130, 116, 188, 159, 86, 164, 100, 109, 198, 173,
+    186, 3, 64, 52, 217, 226, 250, 124, 123, 5, 202, 38, 147, 118, 126, 255, 82, 85, 212, 207, 206,
+    59, 227, 47, 16, 58, 17, 182, 189, 28, 42, 223, 183, 170, 213, 119, 248, 152, 2, 44, 154, 163,
+    70, 221, 153, 101, 155, 167, 43, 172, 9, 129, 22, 39, 253, 19, 98, 108, 110, 79, 113, 224, 232,
+    178, 185, 112, 104, 218, 246, 97, 228, 251, 34, 242, 193, 238, 210, 144, 12, 191, 179, 162,
+    241, 81, 51, 145, 235, 249, 14, 239, 107, 49, 192, 214, 31, 181, 199, 106, 157, 184, 84, 204,
+    176, 115, 121, 50, 45, 127, 4, 150, 254, 138, 236, 205, 93, 222, 114, 67, 29, 24, 72, 243, 141,
+    128, 195, 78, 66, 215, 61, 156, 180,
+];
+
+fn hash(value: i32) -> u8 {
+    PERMUTATION[(value & 255) as usize]
+}
+
+/// Smoothstep curve used to ease interpolation between lattice points.
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + t * (b - a)
+}
+
+/// Dot product of the offset from a lattice point with one of 32 gradients picked by `hash`.
+fn gradient(hash: u8, x: f32, y: f32, z: f32, w: f32) -> f32 {
+    let h = hash & 31;
+
+    let a = if h < 24 { x } else { y };
+    let b = if h < 16 { y } else { z };
+    let c = if h < 8 { z } else { w };
+
+    let a = if h & 1 == 0 { a } else { -a };
+    let b = if h & 2 == 0 { b } else { -b };
+    let c = if h & 4 == 0 { c } else { -c };
+
+    a + b + c
+}
+
+/// Sample 4D Perlin noise. The result is roughly in the range `-1.0..=1.0` and is zero at every
+/// integer lattice point.
+pub fn perlin(x: f32, y: f32, z: f32, w: f32) -> f32 {
+    let (xf, yf, zf, wf) = (x.floor(), y.floor(), z.floor(), w.floor());
+    let (xi, yi, zi, wi) = (xf as i32, yf as i32, zf as i32, wf as i32);
+    let (x, y, z, w) = (x - xf, y - yf, z - zf, w - wf);
+
+    // Gradient contribution of each of the 16 corners of the surrounding hypercube. Bit 0 of the
+    // index selects the X corner, bit 1 Y, and so on.
+    let mut corners = [0.0f32; 16];
+
+    for (idx, corner) in corners.iter_mut().enumerate() {
+        let (dx, dy, dz, dw) = (
+            (idx & 1) as i32,
+            (idx >> 1 & 1) as i32,
+            (idx >> 2 & 1) as i32,
+            (idx >> 3 & 1) as i32,
+        );
+
+        let h = hash(hash(hash(hash(xi + dx) as i32 + yi + dy) as i32 + zi + dz) as i32 + wi + dw);
+
+        *corner = gradient(
+            h,
+            x - dx as f32,
+            y - dy as f32,
+            z - dz as f32,
+            w - dw as f32,
+        );
+    }
+
+    // Collapse one axis at a time, halving the number of values each pass
+    let mut len = corners.len();
+
+    for t in [fade(x), fade(y), fade(z), fade(w)].iter() {
+        len /= 2;
+
+        for idx in 0..len {
+            corners[idx] = lerp(corners[idx * 2], corners[idx * 2 + 1], *t);
+        }
+    }
+
+    corners[0]
+}
+
+/// Fractal noise made by summing `octaves` layers of [`perlin`] noise, each at double the
+/// frequency and half the amplitude of the last. The result is normalised to roughly
+/// `-1.0..=1.0`.
+pub fn fbm(x: f32, y: f32, z: f32, w: f32, octaves: u8) -> f32 {
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut max = 0.0;
+
+    for _ in 0..octaves.max(1) {
+        total += perlin(x * frequency, y * frequency, z * frequency, w * frequency) * amplitude;
+
+        max += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+
+    total / max
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_at_lattice_points() {
+        for i in -3..3 {
+            let i = i as f32;
+
+            assert_eq!(perlin(i, i * 2.0, 0.0, -i), 0.0);
+        }
+    }
+
+    #[test]
+    fn bounded_and_smooth() {
+        let sample = |t: f32| perlin(t * 0.7, t * 0.3 + 1.1, 2.0 - t * 0.5, t);
+
+        let mut prev = sample(0.0);
+
+        for step in 1..2000 {
+            let t = step as f32 * 0.01;
+
+            let value = sample(t);
+
+            assert!(value.abs() <= 1.0, "{} out of range at {}", value, t);
+            assert!((value - prev).abs() < 0.1, "discontinuity at {}", t);
+
+            prev = value;
+        }
+    }
+
+    #[test]
+    fn octaves_normalised() {
+        for step in 0..500 {
+            let t = step as f32 * 0.037;
+
+            assert!(fbm(t, -t, t * 0.5, 0.3, 4).abs() <= 1.0);
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use crate::apa106led::Apa106Led;
+
+/// A fixed list of colours to pick from or blend between.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Palette {
+    colours: &'static [Apa106Led],
+}
+
+impl Palette {
+    /// Warm yellows and greens.
+    pub const FIREFLY: Palette = Palette::new(&[
+        Apa106Led {
+            red: 255,
+            green: 183,
+            blue: 76,
+        },
+        Apa106Led {
+            red: 180,
+            green: 255,
+            blue: 40,
+        },
+        Apa106Led {
+            red: 255,
+            green: 120,
+            blue: 10,
+        },
+    ]);
+
+    /// Black - red - orange - yellow - white heat gradient.
+    pub const FIRE: Palette = Palette::new(&[
+        Apa106Led {
+            red: 0,
+            green: 0,
+            blue: 0,
+        },
+        Apa106Led {
+            red: 160,
+            green: 0,
+            blue: 0,
+        },
+        Apa106Led {
+            red: 255,
+            green: 80,
+            blue: 0,
+        },
+        Apa106Led {
+            red: 255,
+            green: 200,
+            blue: 0,
+        },
+        Apa106Led {
+            red: 255,
+            green: 255,
+            blue: 180,
+        },
+    ]);
+
+    /// Deep blue through cyan and magenta.
+    pub const OCEAN: Palette = Palette::new(&[
+        Apa106Led {
+            red: 0,
+            green: 10,
+            blue: 80,
+        },
+        Apa106Led {
+            red: 0,
+            green: 160,
+            blue: 200,
+        },
+        Apa106Led {
+            red: 120,
+            green: 0,
+            blue: 255,
+        },
+        Apa106Led {
+            red: 255,
+            green: 0,
+            blue: 120,
+        },
+    ]);
+
+    /// Red - green - white Christmas colours.
+    pub const CHRISTMAS: Palette = Palette::new(&[
+        Apa106Led {
+            red: 255,
+            green: 0,
+            blue: 0,
+        },
+        Apa106Led {
+            red: 0,
+            green: 255,
+            blue: 0,
+        },
+        Apa106Led {
+            red: 255,
+            green: 255,
+            blue: 255,
+        },
+    ]);
+
+    /// Create a palette. Panics if `colours` is empty.
+    pub const fn new(colours: &'static [Apa106Led]) -> Self {
+        assert!(
+            !colours.is_empty(),
+            "Palette must contain at least one colour"
+        );
+
+        Self { colours }
+    }
+
+    pub fn len(&self) -> usize {
+        self.colours.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colours.is_empty()
+    }
+
+    /// Pick a colour by index, wrapping around at the end of the palette.
+    pub fn get(&self, idx: usize) -> Apa106Led {
+        self.colours[idx % self.colours.len()]
+    }
+
+    /// Blend smoothly along the palette, where `0.0` is the first colour and `1.0` the last.
+    /// Positions outside this range are clamped.
+    pub fn sample(&self, position: f32) -> Apa106Led {
+        let last = self.colours.len() - 1;
+
+        let position = position.clamp(0.0, 1.0) * last as f32;
+
+        let idx = (position as usize).min(last);
+        let next = (idx + 1).min(last);
+
+        self.colours[idx].lerp(self.colours[next], position - idx as f32)
+    }
+
+    /// Blend around the palette as a colour wheel, where the last colour blends back into the
+    /// first. `0.0` and `1.0` are both the first colour, and positions outside this range wrap.
+    pub fn sample_wrapping(&self, position: f32) -> Apa106Led {
+        let len = self.colours.len();
+
+        let position = (position % 1.0 + 1.0) % 1.0 * len as f32;
+
+        let idx = (position as usize).min(len - 1);
+
+        self.get(idx).lerp(self.get(idx + 1), position - idx as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_ends() {
+        let palette = Palette::FIRE;
+
+        assert_eq!(palette.sample(0.0), palette.get(0));
+        assert_eq!(palette.sample(-1.0), palette.get(0));
+        assert_eq!(palette.sample(1.0), palette.get(palette.len() - 1));
+        assert_eq!(palette.sample(2.0), palette.get(palette.len() - 1));
+    }
+
+    #[test]
+    fn sample_between() {
+        const BLACK_WHITE: Palette = Palette::new(&[
+            Apa106Led {
+                red: 0,
+                green: 0,
+                blue: 0,
+            },
+            Apa106Led {
+                red: 200,
+                green: 200,
+                blue: 200,
+            },
+        ]);
+
+        assert_eq!(
+            BLACK_WHITE.sample(0.5),
+            Apa106Led {
+                red: 100,
+                green: 100,
+                blue: 100
+            }
+        );
+        assert_eq!(BLACK_WHITE.get(3), BLACK_WHITE.get(1));
+    }
+
+    #[test]
+    fn sample_wrapping() {
+        let palette = Palette::CHRISTMAS;
+
+        assert_eq!(palette.sample_wrapping(0.0), palette.get(0));
+        assert_eq!(palette.sample_wrapping(1.0), palette.get(0));
+        assert_eq!(
+            palette.sample_wrapping(-0.5),
+            Apa106Led {
+                red: 127,
+                green: 255,
+                blue: 127
+            }
+        );
+        assert_eq!(
+            palette.sample_wrapping(5.0 / 6.0),
+            Apa106Led {
+                red: 255,
+                green: 127,
+                blue: 127
+            }
+        );
+    }
+}
@@ -0,0 +1,371 @@
+//! Fixed capacity particle system for effects like sparks, rain and fireworks.
+//!
+//! Particles live in a [`ParticleSystem`] with room for `N` particles, so no allocator is needed.
+//! New particles are created by an [`Emitter`] and are removed at the end of their lifetime.
+
+use crate::{apa106led::Apa106Led, cube::Cube, palette::Palette, vec3::Vec3};
+use rand::Rng;
+
+/// How the colour of every particle in a [`ParticleSystem`] changes over its lifetime.
+#[derive(Copy, Clone, Debug)]
+pub enum ColourOverLife {
+    /// Start at the particle's own colour and fade linearly to black.
+    Fade,
+
+    /// Blend along a palette from the first colour at birth to the last colour at death. The
+    /// particle's own colour is ignored.
+    Palette(Palette),
+}
+
+impl ColourOverLife {
+    /// Colour of a particle created with `colour` at a given point in its life, where `0.0` is
+    /// birth and `1.0` is death.
+    pub fn colour_at(&self, colour: Apa106Led, life: f32) -> Apa106Led {
+        match self {
+            Self::Fade => colour.fade(1.0 - life.clamp(0.0, 1.0)),
+            Self::Palette(palette) => palette.sample(life),
+        }
+    }
+}
+
+/// A single particle.
+///
+/// Systems hold many of these with no allocator to fall back on, so the colour is stored inline
+/// and times are kept to 16 bits.
+#[derive(Copy, Clone, Debug)]
+pub struct Particle {
+    /// Position in voxels.
+    pub position: Vec3,
+
+    /// Voxels per second.
+    pub velocity: Vec3,
+
+    /// Time since the particle was created, ms.
+    pub age: u16,
+
+    /// Age at which the particle is removed, ms.
+    pub lifetime: u16,
+
+    /// Colour at birth.
+    pub colour: Apa106Led,
+}
+
+impl Particle {
+    /// Move the particle on by `dt` ms, accelerating it by `gravity` in voxels per second
+    /// squared.
+    pub fn step(&mut self, gravity: Vec3, dt: u32) {
+        let seconds = dt as f32 / 1000.0;
+
+        self.velocity += gravity * seconds;
+        self.position += self.velocity * seconds;
+        self.age = self.age.saturating_add(dt.min(u16::MAX as u32) as u16);
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.age < self.lifetime
+    }
+
+    /// Point in the particle's life, where `0.0` is birth and `1.0` is death.
+    pub fn life(&self) -> f32 {
+        self.age as f32 / self.lifetime.max(1) as f32
+    }
+}
+
+/// Creates particles with randomised starting velocities.
+#[derive(Copy, Clone, Debug)]
+pub struct Emitter {
+    /// Starting position of new particles, in voxels.
+    pub position: Vec3,
+
+    /// Starting velocity of new particles before any spread is added, voxels per second.
+    pub velocity: Vec3,
+
+    /// Speed added to each particle in a random direction, voxels per second.
+    pub spread: f32,
+
+    /// Lifetime of new particles, ms.
+    pub lifetime: u16,
+
+    /// Colour of new particles.
+    pub colour: Apa106Led,
+
+    /// Average number of particles created per second by [`ParticleSystem::emit_over`].
+    pub rate: f32,
+}
+
+impl Emitter {
+    /// Create a single new particle.
+    pub fn particle(&self, rng: &mut impl Rng) -> Particle {
+        let direction = Vec3::new(
+            rng.gen_range(-1.0, 1.0),
+            rng.gen_range(-1.0, 1.0),
+            rng.gen_range(-1.0, 1.0),
+        )
+        .normalize()
+        .unwrap_or(Vec3::ZERO);
+
+        Particle {
+            position: self.position,
+            velocity: self.velocity + direction * self.spread,
+            age: 0,
+            lifetime: self.lifetime,
+            colour: self.colour,
+        }
+    }
+}
+
+/// A fixed capacity collection of particles that all share the same gravity.
+#[derive(Clone, Debug)]
+pub struct ParticleSystem<const N: usize> {
+    particles: [Option<Particle>; N],
+
+    /// Acceleration applied to every particle, voxels per second squared.
+    pub gravity: Vec3,
+
+    /// How every particle's colour changes as it ages.
+    pub colour: ColourOverLife,
+}
+
+impl<const N: usize> ParticleSystem<N> {
+    /// Create an empty system whose particles fade to black over their lifetime.
+    pub fn new(gravity: Vec3) -> Self {
+        Self {
+            particles: [None; N],
+            gravity,
+            colour: ColourOverLife::Fade,
+        }
+    }
+
+    /// Current colour of a particle, following this system's [`ColourOverLife`].
+    pub fn colour_of(&self, particle: &Particle) -> Apa106Led {
+        self.colour.colour_at(particle.colour, particle.life())
+    }
+
+    /// Iterate over every live particle.
+    pub fn iter(&self) -> impl Iterator<Item = &Particle> {
+        self.particles.iter().flatten()
+    }
+
+    /// Number of live particles.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.iter().all(Option::is_none)
+    }
+
+    /// Remove every particle.
+    pub fn clear(&mut self) {
+        self.particles = [None; N];
+    }
+
+    /// Add a particle, returning `false` if the system is full.
+    pub fn spawn(&mut self, particle: Particle) -> bool {
+        match self.particles.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot) => {
+                *slot = Some(particle);
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Create up to `count` particles from an emitter. Returns the number created, which is less
+    /// than `count` if the system fills up.
+    pub fn emit(&mut self, emitter: &Emitter, count: usize, rng: &mut impl Rng) -> usize {
+        (0..count)
+            .take_while(|_| self.spawn(emitter.particle(rng)))
+            .count()
+    }
+
+    /// Create particles from an emitter at its average [`rate`](Emitter::rate) for `dt` ms.
+    pub fn emit_over(&mut self, emitter: &Emitter, dt: u32, rng: &mut impl Rng) {
+        for _ in 0..spawn_count(emitter.rate, dt, N, rng) {
+            self.spawn(emitter.particle(rng));
+        }
+    }
+
+    /// Move every particle on by `dt` ms and remove any that have reached the end of their
+    /// lifetime.
+    pub fn update(&mut self, dt: u32) {
+        let gravity = self.gravity;
+
+        for slot in self.particles.iter_mut() {
+            if let Some(particle) = slot {
+                particle.step(gravity, dt);
+
+                if !particle.is_alive() {
+                    *slot = None;
+                }
+            }
+        }
+    }
+
+    /// Add every particle inside the cube to a frame, saturating at full brightness.
+    pub fn render_frame(&self, frame: &mut [Apa106Led; 64]) {
+        for particle in self.iter() {
+            if let Some(voxel) = particle.position.to_voxel() {
+                let idx = voxel.into_index_unchecked();
+
+                frame[idx] = frame[idx] + self.colour_of(particle);
+            }
+        }
+    }
+
+    /// Add up every particle in the voxel at a frame index, saturating at full brightness.
+    ///
+    /// Use this to draw particles without keeping a frame around to [`render_frame`] into.
+    ///
+    /// [`render_frame`]: ParticleSystem::render_frame
+    pub fn pixel_at(&self, idx: usize) -> Apa106Led {
+        self.iter()
+            .filter(|particle| {
+                particle
+                    .position
+                    .to_voxel()
+                    .map(|voxel| voxel.into_index_unchecked())
+                    == Some(idx)
+            })
+            .fold(Apa106Led::OFF, |colour, particle| {
+                colour + self.colour_of(particle)
+            })
+    }
+
+    /// Add every particle inside the cube to the cube's frame.
+    pub fn render(&self, cube: &mut Cube) {
+        self.render_frame(cube.frame_mut());
+    }
+}
+
+/// Number of things to spawn over `dt` ms, to spawn an average of `rate` per second, up to `max`.
+///
+/// Every whole expected spawn happens, and any fraction left over is a single chance, so low rates
+/// still spawn now and then however short the frames are. Negative or NaN rates spawn nothing.
+pub fn spawn_count(rate: f32, dt: u32, max: usize, rng: &mut impl Rng) -> usize {
+    let expected = rate * dt as f32 / 1000.0;
+
+    if expected.is_nan() || expected <= 0.0 {
+        return 0;
+    }
+
+    if expected >= max as f32 {
+        return max;
+    }
+
+    let whole = expected as usize;
+    let extra = rng.gen::<f32>() < expected - whole as f32;
+
+    (whole + extra as usize).min(max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apa106led::OFF;
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    const WHITE: Apa106Led = Apa106Led {
+        red: 255,
+        green: 255,
+        blue: 255,
+    };
+
+    fn emitter() -> Emitter {
+        Emitter {
+            position: Vec3::CENTRE,
+            velocity: Vec3::ZERO,
+            spread: 0.0,
+            lifetime: 100,
+            colour: WHITE,
+            rate: 0.0,
+        }
+    }
+
+    #[test]
+    fn fills_up_and_expires() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut system = ParticleSystem::<4>::new(Vec3::ZERO);
+
+        assert_eq!(system.emit(&emitter(), 6, &mut rng), 4);
+        assert_eq!(system.len(), 4);
+
+        system.update(50);
+
+        assert_eq!(system.len(), 4);
+
+        system.update(50);
+
+        assert!(system.is_empty());
+    }
+
+    #[test]
+    fn gravity_and_colour() {
+        let mut particle = emitter().particle(&mut SmallRng::seed_from_u64(1));
+
+        particle.step(Vec3::new(0.0, 0.0, -10.0), 50);
+
+        assert_eq!(particle.velocity, Vec3::new(0.0, 0.0, -0.5));
+        assert_eq!(
+            ColourOverLife::Fade.colour_at(particle.colour, particle.life()),
+            WHITE.fade(0.5)
+        );
+    }
+
+    #[test]
+    fn renders_additively() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut system = ParticleSystem::<4>::new(Vec3::ZERO);
+        let mut cube = Cube::new(255);
+        cube.fill(OFF);
+
+        let mut emitter = emitter();
+        emitter.position = Vec3::ZERO;
+        emitter.colour = Apa106Led {
+            red: 200,
+            green: 10,
+            blue: 0,
+        };
+
+        system.emit(&emitter, 2, &mut rng);
+
+        // Outside the cube, so not drawn
+        emitter.position = Vec3::new(-5.0, 0.0, 0.0);
+        system.emit(&emitter, 1, &mut rng);
+
+        system.render(&mut cube);
+
+        assert_eq!(
+            cube.frame()[0],
+            Apa106Led {
+                red: 255,
+                green: 20,
+                blue: 0
+            }
+        );
+        assert_eq!(cube.frame().iter().filter(|led| **led != OFF).count(), 1);
+        assert_eq!(system.pixel_at(0), cube.frame()[0]);
+        assert_eq!(system.pixel_at(1), OFF);
+    }
+
+    #[test]
+    fn spawn_rate() {
+        let mut rng = SmallRng::seed_from_u64(1);
+
+        // Every whole expected spawn always happens
+        assert_eq!(spawn_count(1000.0, 3, 8, &mut rng), 3);
+        assert_eq!(spawn_count(0.0, 1000, 8, &mut rng), 0);
+
+        // Never more than there's room for, and bad rates don't hang
+        assert_eq!(spawn_count(1e9, 1000, 8, &mut rng), 8);
+        assert_eq!(spawn_count(f32::INFINITY, 33, 8, &mut rng), 8);
+        assert_eq!(spawn_count(f32::NAN, 33, 8, &mut rng), 0);
+        assert_eq!(spawn_count(-10.0, 33, 8, &mut rng), 0);
+
+        let total: usize = (0..1000).map(|_| spawn_count(10.0, 33, 8, &mut rng)).sum();
+
+        // 10 per second over 33 seconds
+        assert!((280..380).contains(&total));
+    }
+}
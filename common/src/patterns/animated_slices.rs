@@ -0,0 +1,138 @@
+use crate::{
+    apa106led::{Apa106Led, OFF},
+    patterns::{slices::MAX_FADE_TIME, PatternUpdate},
+    voxel::{Axis, Voxel},
+};
+
+/// Each stage fades up four slices one after the other, then fades them all out together.
+const STAGE_STEPS: u32 = 5;
+
+/// Axis and colour of each stage, in order.
+const STAGES: [(Axis, Apa106Led, bool); 3] = [
+    (
+        Axis::Y,
+        Apa106Led {
+            red: 255,
+            green: 0,
+            blue: 0,
+        },
+        false,
+    ),
+    (
+        Axis::X,
+        Apa106Led {
+            red: 0,
+            green: 255,
+            blue: 0,
+        },
+        false,
+    ),
+    // Layers fill from the top down
+    (
+        Axis::Z,
+        Apa106Led {
+            red: 255,
+            green: 255,
+            blue: 255,
+        },
+        true,
+    ),
+];
+
+/// Red panels, then green slices, then white layers fade up one at a time before fading out.
+#[derive(Clone, Debug)]
+pub struct AnimatedSlices {
+    /// Time for one slice to fade up, ms.
+    fade_time: u32,
+}
+
+impl Default for AnimatedSlices {
+    fn default() -> Self {
+        Self { fade_time: 1000 }
+    }
+}
+
+impl AnimatedSlices {
+    /// Set the time in ms for each slice to fade up, from 1 ms to one minute. The fade out at the
+    /// end of each colour takes the same time.
+    pub fn with_fade_time(mut self, fade_time: u32) -> Self {
+        self.fade_time = fade_time.clamp(1, MAX_FADE_TIME);
+
+        self
+    }
+
+    fn cycle_time(&self) -> u32 {
+        self.fade_time * STAGE_STEPS * STAGES.len() as u32
+    }
+}
+
+impl PatternUpdate for AnimatedSlices {
+    fn pixel_at(&self, idx: usize, time: u32) -> Apa106Led {
+        let time = time % self.cycle_time();
+
+        let step = time / self.fade_time;
+        let progress = (time % self.fade_time) as f32 / self.fade_time as f32;
+
+        let (axis, colour, reverse) = STAGES[(step / STAGE_STEPS) as usize];
+        let step = (step % STAGE_STEPS) as u8;
+
+        // Last step of each stage fades everything out
+        if step == 4 {
+            return colour.fade(1.0 - progress);
+        }
+
+        let coord = axis.coord(Voxel::from_index_unchecked(idx));
+        let slice = if reverse { 3 - coord } else { coord };
+
+        if slice < step {
+            colour
+        } else if slice == step {
+            colour.fade(progress)
+        } else {
+            OFF
+        }
+    }
+
+    fn cycle_length(&self) -> Option<u32> {
+        Some(self.cycle_time())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stages() {
+        let slices = AnimatedSlices::default();
+        let t = slices.fade_time;
+
+        let red = |time| {
+            (0..64)
+                .filter(|idx| slices.pixel_at(*idx, time).red == 255)
+                .count()
+        };
+
+        assert_eq!(red(0), 0);
+        assert_eq!(red(t), 16);
+        assert_eq!(red(4 * t - 1), 48);
+        assert!((0..64).all(|idx| slices.pixel_at(idx, 5 * t - 1).red < 5));
+
+        // First white layer is the top one
+        let top = Voxel { x: 0, y: 0, z: 3 }.into_index_unchecked();
+
+        assert_eq!(slices.pixel_at(top, 11 * t), STAGES[2].1);
+        assert_eq!(slices.pixel_at(0, 11 * t), OFF);
+    }
+
+    #[test]
+    fn long_fade_time() {
+        let slices = AnimatedSlices::default().with_fade_time(u32::MAX);
+
+        assert_eq!(slices.cycle_length(), Some(MAX_FADE_TIME * 15));
+        assert_eq!(
+            slices.pixel_at(0, u32::MAX),
+            slices.pixel_at(0, u32::MAX % (MAX_FADE_TIME * 15))
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{
+    apa106led::{Apa106Led, OFF, WARM_WHITE},
+    patterns::PatternUpdate,
+    voxel::Voxel,
+};
+
+/// Number of frames in one turn of the blender.
+const FRAMES: u32 = 6;
+
+/// Columns spinning around the vertical axis of the cube, like the blades of a blender.
+///
+/// An inner ring of two columns flips between diagonals while an outer pair of columns travels
+/// around the faces of the cube.
+#[derive(Clone, Debug)]
+pub struct Blender {
+    colour: Apa106Led,
+
+    /// Time each frame is shown for, ms.
+    frame_time: u32,
+}
+
+impl Default for Blender {
+    fn default() -> Self {
+        Self {
+            colour: WARM_WHITE,
+            frame_time: 100,
+        }
+    }
+}
+
+impl Blender {
+    pub fn with_colour(mut self, colour: Apa106Led) -> Self {
+        self.colour = colour;
+
+        self
+    }
+
+    /// Set the time each frame is shown for in ms. One turn is 6 frames.
+    pub fn with_frame_time(mut self, frame_time: u32) -> Self {
+        self.frame_time = frame_time.max(1);
+
+        self
+    }
+}
+
+/// X and Y positions of the lit columns in a given frame.
+fn columns(frame: u8) -> [(u8, u8); 4] {
+    let inner = match frame {
+        0 | 1 | 5 => [(1, 2), (2, 1)],
+        _ => [(1, 1), (2, 2)],
+    };
+
+    let outer = if frame < 4 {
+        [(3 - frame, 0), (frame, 3)]
+    } else {
+        [(0, frame - 3), (3, 3 - (frame - 3))]
+    };
+
+    [inner[0], inner[1], outer[0], outer[1]]
+}
+
+impl PatternUpdate for Blender {
+    fn pixel_at(&self, idx: usize, time: u32) -> Apa106Led {
+        let frame = (time / self.frame_time % FRAMES) as u8;
+
+        let voxel = Voxel::from_index_unchecked(idx);
+
+        if columns(frame).contains(&(voxel.x, voxel.y)) {
+            self.colour
+        } else {
+            OFF
+        }
+    }
+
+    fn cycle_length(&self) -> Option<u32> {
+        Some(self.frame_time * FRAMES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn four_columns_per_frame() {
+        let blender = Blender::default();
+
+        for frame in 0..FRAMES {
+            let time = frame * blender.frame_time;
+
+            let lit = (0..64)
+                .filter(|idx| blender.pixel_at(*idx, time) != OFF)
+                .count();
+
+            assert_eq!(lit, 16, "frame {}", frame);
+        }
+    }
+}
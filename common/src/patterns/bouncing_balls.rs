@@ -0,0 +1,454 @@
+use crate::{
+    apa106led::{Apa106Led, OFF},
+    input::InputEvent,
+    palette::Palette,
+    patterns::{PatternUpdate, Seeded},
+    vec3::Vec3,
+    voxel::Voxel,
+};
+use rand::prelude::*;
+
+/// Largest number of balls a pattern can hold.
+pub const MAX_BALLS: usize = 8;
+
+/// Longest time the physics simulation is advanced by in one go, ms. Longer frames are split
+/// into steps of this size so fast balls don't tunnel through the walls.
+const MAX_STEP: u32 = 10;
+
+/// Longest frame time simulated. Longer gaps between frames are treated as this long instead of
+/// catching up.
+const MAX_FRAME: u32 = 100;
+
+/// Distance from the centre of a ball at which it stops lighting voxels.
+const RADIUS: f32 = 1.0;
+
+/// Balls bouncing slower than this, in voxels per second, are kicked back into the air.
+const REST_SPEED: f32 = 0.75;
+
+/// Speed added away from the floor when a button kicks the balls, voxels per second.
+const KICK_SPEED: f32 = 8.0;
+
+/// Sideways acceleration when an axis is tilted all the way, voxels per second squared.
+const TILT: f32 = 9.0;
+
+const BALL_PALETTE: Palette = Palette::new(&[
+    Apa106Led {
+        red: 255,
+        green: 0,
+        blue: 0,
+    },
+    Apa106Led {
+        red: 0,
+        green: 255,
+        blue: 0,
+    },
+    Apa106Led {
+        red: 0,
+        green: 0,
+        blue: 255,
+    },
+    Apa106Led {
+        red: 255,
+        green: 160,
+        blue: 0,
+    },
+    Apa106Led {
+        red: 200,
+        green: 0,
+        blue: 255,
+    },
+    Apa106Led {
+        red: 0,
+        green: 200,
+        blue: 200,
+    },
+]);
+
+#[derive(Copy, Clone, Debug)]
+struct Ball {
+    /// Position in voxels, where `0.0` and `3.0` are the centres of the outermost voxels.
+    position: Vec3,
+
+    /// Voxels per second.
+    velocity: Vec3,
+
+    colour: Apa106Led,
+}
+
+/// Colour a voxel was last lit with, and how long ago.
+#[derive(Copy, Clone, Debug)]
+struct Trail {
+    colour: Apa106Led,
+
+    /// Time since a ball last lit the voxel, ms. Stops counting at `u16::MAX`.
+    age: u16,
+}
+
+impl Trail {
+    const OFF: Trail = Trail {
+        colour: OFF,
+        age: u16::MAX,
+    };
+
+    /// Brightness after fading linearly to black over `trail_time` ms.
+    fn level(&self, trail_time: u16) -> f32 {
+        if self.age >= trail_time {
+            0.0
+        } else {
+            1.0 - self.age as f32 / trail_time as f32
+        }
+    }
+}
+
+/// Coloured balls bouncing around inside the cube, leaving fading trails behind them.
+///
+/// Balls lose some energy with every bounce. Any ball that comes to rest against a wall is
+/// kicked off in a new random direction.
+///
+/// Pressing any button kicks every ball into the air, and axes 0 and 1 tilt gravity along X and Y.
+#[derive(Clone, Debug)]
+pub struct BouncingBalls {
+    /// Number of balls in use, from 1 to [`MAX_BALLS`].
+    count: usize,
+
+    /// Acceleration applied to every ball, voxels per second squared.
+    gravity: Vec3,
+
+    /// Extra acceleration from tilting the input axes, voxels per second squared.
+    tilt: Vec3,
+
+    /// Proportion of speed kept after bouncing off a wall, 0.0 - 1.0.
+    elasticity: f32,
+
+    /// Time for a trail to fade from full brightness to black, ms.
+    trail_time: u16,
+
+    palette: Palette,
+
+    balls: [Ball; MAX_BALLS],
+
+    /// Balls and their fading trails, by frame index.
+    trails: [Trail; 64],
+
+    last_update: u32,
+
+    rng: SmallRng,
+}
+
+impl Default for BouncingBalls {
+    fn default() -> Self {
+        let mut pattern = Self {
+            count: 3,
+            gravity: Vec3::new(0.0, 0.0, -9.0),
+            tilt: Vec3::ZERO,
+            elasticity: 0.85,
+            trail_time: 300,
+            palette: BALL_PALETTE,
+            balls: [Ball {
+                position: Vec3::CENTRE,
+                velocity: Vec3::ZERO,
+                colour: OFF,
+            }; MAX_BALLS],
+            trails: [Trail::OFF; 64],
+            last_update: 0,
+            rng: SmallRng::seed_from_u64(Self::SEED),
+        };
+
+        pattern.launch_all();
+
+        pattern
+    }
+}
+
+impl BouncingBalls {
+    /// Set the number of balls, from 1 to [`MAX_BALLS`].
+    pub fn with_count(mut self, count: usize) -> Self {
+        self.count = count.clamp(1, MAX_BALLS);
+
+        self
+    }
+
+    /// Set the acceleration applied to every ball in voxels per second squared. Point this
+    /// anywhere to change which way is down, or use [`Vec3::ZERO`] to let the balls float.
+    pub fn with_gravity(mut self, gravity: Vec3) -> Self {
+        self.gravity = gravity;
+
+        self
+    }
+
+    /// Set the proportion of speed kept after each bounce, from 0.0 (no bounce) to 1.0 (no
+    /// energy lost).
+    pub fn with_elasticity(mut self, elasticity: f32) -> Self {
+        self.elasticity = elasticity.clamp(0.0, 1.0);
+
+        self
+    }
+
+    /// Set the time in ms for a trail to fade to black, up to `u16::MAX`. Use 0 to disable
+    /// trails.
+    pub fn with_trail_time(mut self, trail_time: u32) -> Self {
+        self.trail_time = trail_time.min(u16::MAX as u32) as u16;
+
+        self
+    }
+
+    /// Set the colours balls are given, in order.
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+
+        self.launch_all();
+
+        self
+    }
+
+    /// Place every ball at a random position with a random velocity.
+    fn launch_all(&mut self) {
+        for idx in 0..MAX_BALLS {
+            let position = Vec3::new(
+                self.rng.gen_range(0.0, 3.0),
+                self.rng.gen_range(0.0, 3.0),
+                self.rng.gen_range(0.0, 3.0),
+            );
+
+            self.balls[idx] = Ball {
+                position,
+                velocity: self.random_velocity(),
+                colour: self.palette.get(idx),
+            };
+        }
+    }
+
+    fn random_velocity(&mut self) -> Vec3 {
+        Vec3::new(
+            self.rng.gen_range(-4.0, 4.0),
+            self.rng.gen_range(-4.0, 4.0),
+            self.rng.gen_range(-4.0, 4.0),
+        )
+    }
+
+    /// Throw every ball away from the floor in a random direction.
+    fn kick(&mut self) {
+        let up = -self
+            .gravity
+            .normalize()
+            .unwrap_or(Vec3::new(0.0, 0.0, -1.0));
+
+        for idx in 0..self.count {
+            let kick = self.random_velocity() + up * KICK_SPEED;
+
+            self.balls[idx].velocity += kick;
+        }
+    }
+
+    /// Advance the physics simulation by `dt` seconds.
+    fn step(&mut self, dt: f32) {
+        let gravity = self.gravity + self.tilt;
+
+        for idx in 0..self.count {
+            let mut ball = self.balls[idx];
+
+            ball.velocity += gravity * dt;
+            ball.position += ball.velocity * dt;
+
+            // Non short circuiting so every axis is checked
+            let bounced = bounce(&mut ball.position.x, &mut ball.velocity.x, self.elasticity)
+                | bounce(&mut ball.position.y, &mut ball.velocity.y, self.elasticity)
+                | bounce(&mut ball.position.z, &mut ball.velocity.z, self.elasticity);
+
+            if bounced && ball.velocity.length_squared() < REST_SPEED * REST_SPEED {
+                ball.velocity =
+                    self.random_velocity() - gravity.normalize().unwrap_or(Vec3::ZERO) * 4.0;
+            }
+
+            self.balls[idx] = ball;
+        }
+    }
+
+    /// Age the trails by `dt` ms and draw the balls on top.
+    fn render(&mut self, dt: u32) {
+        let dt = dt.min(u16::MAX as u32) as u16;
+        let trail_time = self.trail_time;
+
+        for (idx, trail) in self.trails.iter_mut().enumerate() {
+            let voxel = Vec3::from(Voxel::from_index_unchecked(idx));
+
+            let lit = self.balls[0..self.count].iter().fold(OFF, |colour, ball| {
+                let level = 1.0 - voxel.distance(ball.position) / RADIUS;
+
+                if level > 0.0 {
+                    colour + ball.colour.fade(level)
+                } else {
+                    colour
+                }
+            });
+
+            trail.age = trail.age.saturating_add(dt);
+
+            // Restart the trail from whatever's brighter, the ball or what's left of the old trail
+            if lit != OFF {
+                *trail = Trail {
+                    colour: brighter(trail.colour.fade(trail.level(trail_time)), lit),
+                    age: 0,
+                };
+            }
+        }
+    }
+}
+
+/// Reflect a ball off the walls at `0.0` and `3.0` along one axis, scaling its speed by
+/// `elasticity`. Returns whether the ball bounced.
+fn bounce(position: &mut f32, velocity: &mut f32, elasticity: f32) -> bool {
+    let wall = if *position < 0.0 {
+        0.0
+    } else if *position > 3.0 {
+        3.0
+    } else {
+        return false;
+    };
+
+    *position = (wall - (*position - wall) * elasticity).clamp(0.0, 3.0);
+    *velocity = -*velocity * elasticity;
+
+    true
+}
+
+/// Brightest of each channel of two colours.
+fn brighter(a: Apa106Led, b: Apa106Led) -> Apa106Led {
+    Apa106Led {
+        red: a.red.max(b.red),
+        green: a.green.max(b.green),
+        blue: a.blue.max(b.blue),
+    }
+}
+
+impl Seeded for BouncingBalls {
+    const SEED: u64 = 0xba11_ba11_ba11_ba11;
+
+    fn set_seed(&mut self, seed: u64) {
+        self.rng = SmallRng::seed_from_u64(seed);
+
+        self.launch_all();
+    }
+}
+
+impl PatternUpdate for BouncingBalls {
+    fn update(&mut self, time: u32) {
+        // Stand still if time goes backwards
+        let dt = time.saturating_sub(self.last_update).min(MAX_FRAME);
+        self.last_update = time;
+
+        let mut remaining = dt;
+
+        while remaining > 0 {
+            let step = remaining.min(MAX_STEP);
+
+            self.step(step as f32 / 1000.0);
+
+            remaining -= step;
+        }
+
+        self.render(dt);
+    }
+
+    fn pixel_at(&self, idx: usize, _time: u32) -> Apa106Led {
+        let trail = self.trails[idx];
+
+        trail.colour.fade(trail.level(self.trail_time))
+    }
+
+    fn input(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::ButtonDown(_) => self.kick(),
+            InputEvent::Axis { axis: 0, value } => self.tilt.x = value.clamp(-1.0, 1.0) * TILT,
+            InputEvent::Axis { axis: 1, value } => self.tilt.y = value.clamp(-1.0, 1.0) * TILT,
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stays_inside() {
+        let mut balls = BouncingBalls::default().with_count(MAX_BALLS);
+
+        for time in (0..20_000).step_by(16) {
+            balls.update(time);
+
+            for ball in balls.balls.iter() {
+                assert!(ball.position.to_voxel().is_some(), "{:?}", ball);
+            }
+        }
+
+        assert!((0..64).any(|idx| balls.pixel_at(idx, 0) != OFF));
+    }
+
+    #[test]
+    fn loses_energy_on_bounce() {
+        let mut balls = BouncingBalls::default()
+            .with_count(1)
+            .with_gravity(Vec3::ZERO)
+            .with_elasticity(0.5);
+
+        balls.balls[0].position = Vec3::new(1.5, 1.5, 0.1);
+        balls.balls[0].velocity = Vec3::new(0.0, 0.0, -4.0);
+
+        balls.step(0.05);
+
+        assert_eq!(balls.balls[0].velocity, Vec3::new(0.0, 0.0, 2.0));
+        assert!(balls.balls[0].position.z >= 0.0);
+    }
+
+    #[test]
+    fn trails_fade() {
+        let mut balls = BouncingBalls::default().with_count(1);
+
+        balls.trails = [Trail {
+            colour: Apa106Led::WARM_WHITE,
+            age: 0,
+        }; 64];
+        balls.balls[0].position = Vec3::new(-10.0, -10.0, -10.0);
+
+        // Fade rate mustn't depend on frame rate, so take many small steps
+        let frames = balls.trail_time as u32 / 16;
+
+        for _ in 0..frames {
+            balls.render(16);
+        }
+
+        assert!((0..64).all(|idx| {
+            let pixel = balls.pixel_at(idx, 0);
+
+            pixel.red < 255 && pixel != OFF
+        }));
+
+        balls.render(16);
+
+        assert!((0..64).all(|idx| balls.pixel_at(idx, 0) == OFF));
+    }
+
+    #[test]
+    fn reacts_to_input() {
+        let mut balls = BouncingBalls::default()
+            .with_count(1)
+            .with_gravity(Vec3::ZERO);
+
+        balls.balls[0].position = Vec3::CENTRE;
+        balls.balls[0].velocity = Vec3::ZERO;
+
+        balls.input(InputEvent::Axis {
+            axis: 0,
+            value: 1.0,
+        });
+        balls.step(0.01);
+
+        assert!(balls.balls[0].velocity.x > 0.0);
+        assert_eq!(balls.balls[0].velocity.y, 0.0);
+
+        balls.input(InputEvent::ButtonDown(0));
+
+        assert!(balls.balls[0].velocity.z >= KICK_SPEED - 4.0);
+    }
+}
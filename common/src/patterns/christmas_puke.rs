@@ -1,54 +1,41 @@
-use crate::{apa106led::Apa106Led, patterns::PatternUpdate};
+use crate::{apa106led::Apa106Led, palette::Palette, patterns::PatternUpdate};
 
 #[derive(Clone, Debug)]
 pub struct ChristmasPuke {
+    /// Time for the colour wheel to make one full turn, ms.
     duration: u32,
+
+    /// Colours blended around the wheel, red - green - white by default.
+    palette: Palette,
 }
 
 impl ChristmasPuke {
-    // Red - green - white colour wheel
-    fn wheel(wheelpos: u8) -> Apa106Led {
-        let mut thingy = wheelpos;
+    /// Set the cycle time in ms. Shorter durations give faster colour changes.
+    pub fn with_duration(mut self, duration: u32) -> Self {
+        self.duration = duration.max(1);
 
-        // Ramp red down to 0, green up to 255
-        if thingy < 85 {
-            Apa106Led {
-                red: 255 - thingy * 3,
-                green: thingy * 3,
-                blue: 0,
-            }
-        } else if thingy < 170 {
-            // Ramp red and blue up, leave green at 255
-            thingy -= 85;
+        self
+    }
 
-            Apa106Led {
-                red: thingy * 3,
-                green: 255,
-                blue: thingy * 3,
-            }
-        } else {
-            // Ramp green and blue down, leave red at 255
-            thingy -= 170;
+    /// Set the colours to blend around, in order.
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
 
-            Apa106Led {
-                red: 255,
-                green: 255 - thingy * 3,
-                blue: 255 - thingy * 3,
-            }
-        }
+        self
     }
 }
 
 impl Default for ChristmasPuke {
     fn default() -> Self {
-        Self { duration: 4000 }
+        Self {
+            duration: 4000,
+            palette: Palette::CHRISTMAS,
+        }
     }
 }
 
 impl PatternUpdate for ChristmasPuke {
-    type CycleCounter = u32;
-
-    fn pixel_at(&mut self, idx: usize, time: u32) -> Apa106Led {
+    fn pixel_at(&self, idx: usize, time: u32) -> Apa106Led {
         let pos = time % self.duration;
         let scaler = self.duration as f32 / 255.0;
 
@@ -61,10 +48,10 @@ impl PatternUpdate for ChristmasPuke {
         // Scale to 0 -> 255 for the u8 wheel input
         let wheelpos = (wheelpos as f32 / scaler) as u8;
 
-        Self::wheel(wheelpos)
+        self.palette.sample_wrapping(wheelpos as f32 / 255.0)
     }
 
-    fn completed_cycles(&self, time: u32) -> Self::CycleCounter {
-        time / self.duration
+    fn cycle_length(&self) -> Option<u32> {
+        Some(self.duration)
     }
 }
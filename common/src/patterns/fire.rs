@@ -0,0 +1,213 @@
+use crate::{
+    apa106led::Apa106Led,
+    clock::FixedStep,
+    palette::Palette,
+    patterns::{PatternUpdate, Seeded},
+    voxel::Voxel,
+};
+use rand::prelude::*;
+
+/// Largest number of simulation steps run in one frame. Jumps further forward than this skip
+/// ahead instead of catching up.
+const MAX_STEPS: u32 = 64;
+
+/// Flames rising from the bottom of the cube.
+///
+/// Heat is added at random to the bottom layer, then rises and cools through the cube with a bit
+/// of sideways turbulence. The simulation runs in fixed time steps using integer maths, so it
+/// produces the same frames for the same seed and frame times.
+#[derive(Clone, Debug)]
+pub struct Fire {
+    /// Largest amount of heat each voxel loses per step.
+    cooling: u8,
+
+    /// Chance out of 255 that each bottom layer voxel flares up per step.
+    sparking: u8,
+
+    palette: Palette,
+
+    /// Heat of each voxel by frame index.
+    heat: [u8; 64],
+
+    fixed_step: FixedStep,
+
+    rng: SmallRng,
+}
+
+impl Default for Fire {
+    fn default() -> Self {
+        Self {
+            cooling: 40,
+            sparking: 120,
+            palette: Palette::FIRE,
+            heat: [0; 64],
+            fixed_step: FixedStep::new(40, MAX_STEPS),
+            rng: SmallRng::seed_from_u64(Self::SEED),
+        }
+    }
+}
+
+impl Fire {
+    /// Set the time between simulation steps in ms. Smaller values give faster flames.
+    pub fn with_step_time(mut self, step_time: u32) -> Self {
+        self.fixed_step.set_step_time(step_time);
+
+        self
+    }
+
+    /// Set the largest amount of heat each voxel loses per step. Higher values give shorter
+    /// flames.
+    pub fn with_cooling(mut self, cooling: u8) -> Self {
+        self.cooling = cooling;
+
+        self
+    }
+
+    /// Set the chance out of 255 that each voxel in the bottom layer flares up per step.
+    pub fn with_sparking(mut self, sparking: u8) -> Self {
+        self.sparking = sparking;
+
+        self
+    }
+
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+
+        self
+    }
+
+    /// Heat of a voxel, clamping X and Y coordinates to the faces of the cube.
+    fn heat_at(&self, x: i8, y: i8, z: u8) -> u8 {
+        let voxel = Voxel {
+            x: x.clamp(0, 3) as u8,
+            y: y.clamp(0, 3) as u8,
+            z,
+        };
+
+        self.heat[voxel.into_index_unchecked()]
+    }
+
+    /// Advance the simulation by one step.
+    fn step(&mut self) {
+        // Cool everything down a little
+        for heat in self.heat.iter_mut() {
+            let cooling = self.rng.gen_range(0, self.cooling as u16 + 1) as u8;
+
+            *heat = heat.saturating_sub(cooling);
+        }
+
+        // Heat rises from the layer below, drifting sideways at random. Work top down so each
+        // layer reads the layer below before it's updated.
+        for z in (1..4).rev() {
+            for y in 0..4 {
+                for x in 0..4 {
+                    let dx = self.rng.gen_range(-1, 2);
+                    let dy = self.rng.gen_range(-1, 2);
+
+                    let below = self.heat_at(x + dx, y + dy, z - 1) as u16;
+
+                    let idx = Voxel {
+                        x: x as u8,
+                        y: y as u8,
+                        z,
+                    }
+                    .into_index_unchecked();
+
+                    self.heat[idx] = ((below * 2 + self.heat[idx] as u16) / 3) as u8;
+                }
+            }
+        }
+
+        // Randomly flare up voxels in the bottom layer
+        for y in 0..4 {
+            for x in 0..4 {
+                if self.rng.gen_range(0, 255) < self.sparking {
+                    let idx = Voxel { x, y, z: 0 }.into_index_unchecked();
+
+                    let spark = self.rng.gen_range(160, 256) as u8;
+
+                    self.heat[idx] = self.heat[idx].saturating_add(spark);
+                }
+            }
+        }
+    }
+}
+
+impl Seeded for Fire {
+    const SEED: u64 = 0xf12e_f12e_f12e_f12e;
+
+    fn set_seed(&mut self, seed: u64) {
+        self.rng = SmallRng::seed_from_u64(seed);
+    }
+}
+
+impl PatternUpdate for Fire {
+    fn update(&mut self, time: u32) {
+        for _ in self.fixed_step.steps(time) {
+            self.step();
+        }
+    }
+
+    fn pixel_at(&self, idx: usize, _time: u32) -> Apa106Led {
+        self.palette.sample(self.heat[idx] as f32 / 255.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(mut fire: Fire) -> [[Apa106Led; 64]; 4] {
+        let mut frames = [[Apa106Led::OFF; 64]; 4];
+
+        for (frame, time) in frames.iter_mut().zip([0, 500, 1000, 2500].iter()) {
+            fire.update(*time);
+
+            for (idx, pixel) in frame.iter_mut().enumerate() {
+                *pixel = fire.pixel_at(idx, *time);
+            }
+        }
+
+        frames
+    }
+
+    #[test]
+    fn deterministic() {
+        let mut fire = Fire::default().with_seed(1234);
+
+        fire.update(1000);
+
+        // Top layer heat after 26 steps
+        assert_eq!(
+            fire.heat[48..],
+            [129, 135, 129, 134, 155, 130, 139, 111, 165, 135, 153, 152, 141, 130, 170, 167]
+        );
+
+        assert_ne!(
+            frames(Fire::default().with_seed(1234)),
+            frames(Fire::default().with_seed(5678))
+        );
+    }
+
+    #[test]
+    fn hotter_at_the_bottom() {
+        let mut fire = Fire::default();
+
+        fire.update(5000);
+
+        let layer_heat = |z: u8| {
+            (0..16)
+                .map(|i| {
+                    fire.heat[Voxel {
+                        x: i % 4,
+                        y: i / 4,
+                        z,
+                    }
+                    .into_index_unchecked()] as u32
+                })
+                .sum::<u32>()
+        };
+
+        assert!(layer_heat(0) > layer_heat(3));
+    }
+}
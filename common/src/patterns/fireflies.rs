@@ -0,0 +1,285 @@
+use crate::{
+    apa106led::{Apa106Led, OFF},
+    palette::Palette,
+    particles::spawn_count,
+    patterns::{PatternUpdate, Seeded},
+};
+use rand::prelude::*;
+
+/// Brightness over the lifetime of a single firefly.
+#[derive(Copy, Clone, Debug)]
+pub struct Envelope {
+    /// Time to fade up to full brightness, ms.
+    pub attack: u32,
+
+    /// Time to drop from full brightness to the baseline, ms.
+    pub decay: u32,
+
+    /// Brightness to rest at after the initial flash, 0.0 - 1.0.
+    pub baseline: f32,
+
+    /// Time to stay at the baseline brightness, ms.
+    pub hold: u32,
+
+    /// Time to fade from the baseline to black, ms.
+    pub release: u32,
+}
+
+impl Envelope {
+    /// Total lifetime of a firefly, ms. Saturates at `u32::MAX`.
+    pub fn duration(&self) -> u32 {
+        self.attack
+            .saturating_add(self.decay)
+            .saturating_add(self.hold)
+            .saturating_add(self.release)
+    }
+
+    /// Brightness at a given age, or `None` once the firefly has gone out.
+    pub fn level(&self, age: u32) -> Option<f32> {
+        let mut age = age;
+
+        if age < self.attack {
+            return Some(age as f32 / self.attack as f32);
+        }
+        age -= self.attack;
+
+        if age < self.decay {
+            let ratio = age as f32 / self.decay as f32;
+
+            return Some(1.0 - (1.0 - self.baseline) * ratio);
+        }
+        age -= self.decay;
+
+        if age < self.hold {
+            return Some(self.baseline);
+        }
+        age -= self.hold;
+
+        if age < self.release {
+            let ratio = age as f32 / self.release as f32;
+
+            return Some(self.baseline * (1.0 - ratio));
+        }
+
+        None
+    }
+}
+
+impl Default for Envelope {
+    fn default() -> Self {
+        Self {
+            attack: 150,
+            decay: 400,
+            baseline: 0.3,
+            hold: 1000,
+            release: 4000,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Firefly {
+    /// Time the firefly lit up.
+    start: u32,
+
+    colour: Apa106Led,
+}
+
+/// LEDs flash up, drop back to a dim glow, then slowly fade out.
+///
+/// When asked to wind down, no new LEDs light and the pattern finishes once every LED has faded
+/// to black.
+#[derive(Clone, Debug)]
+pub struct Fireflies {
+    /// Average number of fireflies lit per second.
+    spawn_rate: f32,
+
+    palette: Palette,
+
+    envelope: Envelope,
+
+    /// Lit firefly for each voxel, by frame index.
+    fireflies: [Option<Firefly>; 64],
+
+    /// Stop lighting new fireflies.
+    winding_down: bool,
+
+    last_update: u32,
+
+    rng: SmallRng,
+}
+
+impl Default for Fireflies {
+    fn default() -> Self {
+        Self {
+            spawn_rate: 3.0,
+            palette: Palette::FIREFLY,
+            envelope: Envelope::default(),
+            fireflies: [None; 64],
+            winding_down: false,
+            last_update: 0,
+            rng: SmallRng::seed_from_u64(Self::SEED),
+        }
+    }
+}
+
+impl Fireflies {
+    /// Set the average number of fireflies lit per second. Negative rates are treated as 0, and
+    /// infinite or NaN rates are ignored.
+    pub fn with_spawn_rate(mut self, spawn_rate: f32) -> Self {
+        if spawn_rate.is_finite() {
+            self.spawn_rate = spawn_rate.max(0.0);
+        }
+
+        self
+    }
+
+    /// Set the colours fireflies are randomly picked from.
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+
+        self
+    }
+
+    /// Set the brightness of each firefly over its lifetime. An envelope with a total duration of
+    /// zero is ignored, as fireflies must stay lit for at least a millisecond.
+    pub fn with_envelope(mut self, envelope: Envelope) -> Self {
+        if envelope.duration() > 0 {
+            self.envelope = envelope;
+        }
+
+        self
+    }
+
+    /// Light a random dark voxel, if one can be found quickly.
+    fn spawn(&mut self, time: u32) {
+        for _ in 0..4 {
+            let idx = self.rng.gen_range(0, 64);
+
+            if self.fireflies[idx].is_none() {
+                let colour = self.palette.get(self.rng.gen_range(0, self.palette.len()));
+
+                self.fireflies[idx] = Some(Firefly {
+                    start: time,
+                    colour,
+                });
+
+                break;
+            }
+        }
+    }
+}
+
+impl Seeded for Fireflies {
+    const SEED: u64 = 0x0f1e_f11e_5eed_0001;
+
+    fn set_seed(&mut self, seed: u64) {
+        self.rng = SmallRng::seed_from_u64(seed);
+    }
+}
+
+impl PatternUpdate for Fireflies {
+    fn update(&mut self, time: u32) {
+        let delta = time.saturating_sub(self.last_update);
+        self.last_update = time;
+
+        let envelope = self.envelope;
+
+        for firefly in self.fireflies.iter_mut() {
+            if let Some(f) = firefly {
+                if envelope.level(time.saturating_sub(f.start)).is_none() {
+                    *firefly = None;
+                }
+            }
+        }
+
+        if self.winding_down {
+            return;
+        }
+
+        for _ in 0..spawn_count(self.spawn_rate, delta, self.fireflies.len(), &mut self.rng) {
+            self.spawn(time);
+        }
+    }
+
+    fn pixel_at(&self, idx: usize, time: u32) -> Apa106Led {
+        self.fireflies[idx]
+            .and_then(|f| {
+                self.envelope
+                    .level(time.saturating_sub(f.start))
+                    .map(|level| f.colour.fade(level))
+            })
+            .unwrap_or(OFF)
+    }
+
+    fn wind_down(&mut self, _time: u32) {
+        self.winding_down = true;
+    }
+
+    fn is_finished(&self, _time: u32) -> bool {
+        self.winding_down && self.fireflies.iter().all(Option::is_none)
+    }
+
+    fn has_natural_end(&self) -> bool {
+        true
+    }
+
+    fn cycle_length(&self) -> Option<u32> {
+        // One cycle is the lifetime of a single firefly
+        Some(self.envelope.duration())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn envelope() {
+        let envelope = Envelope::default();
+
+        assert_eq!(envelope.level(0), Some(0.0));
+        assert_eq!(envelope.level(envelope.attack), Some(1.0));
+        assert_eq!(
+            envelope.level(envelope.attack + envelope.decay),
+            Some(envelope.baseline)
+        );
+        assert_eq!(envelope.level(envelope.duration()), None);
+
+        let long = Envelope {
+            hold: u32::MAX,
+            ..envelope
+        };
+
+        assert_eq!(long.duration(), u32::MAX);
+        assert_eq!(
+            Fireflies::default().with_envelope(long).cycle_length(),
+            Some(u32::MAX)
+        );
+    }
+
+    #[test]
+    fn winds_down() {
+        let mut fireflies = Fireflies::default().with_spawn_rate(20.0);
+
+        for time in (0..2000).step_by(33) {
+            fireflies.update(time);
+        }
+
+        assert!(fireflies.fireflies.iter().any(Option::is_some));
+
+        fireflies.wind_down(2000);
+
+        assert!(!fireflies.is_finished(2000));
+
+        let end = 2000 + fireflies.envelope.duration();
+
+        for time in (2000..=end).step_by(33) {
+            fireflies.update(time);
+        }
+        fireflies.update(end);
+
+        assert!(fireflies.is_finished(end));
+        assert!((0..64).all(|idx| fireflies.pixel_at(idx, end) == OFF));
+    }
+}
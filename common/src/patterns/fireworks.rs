@@ -0,0 +1,223 @@
+use crate::{
+    apa106led::{Apa106Led, WARM_WHITE},
+    particles::{Emitter, Particle, ParticleSystem},
+    patterns::{PatternUpdate, Seeded},
+    vec3::Vec3,
+};
+use rand::prelude::*;
+
+/// Largest number of sparks in the air at once. Every pattern is stored inline in [`Pattern`], so
+/// this is kept small to not grow every other pattern with it.
+///
+/// [`Pattern`]: crate::patterns::Pattern
+const MAX_SPARKS: usize = 24;
+
+/// Longest frame time simulated. Longer gaps between frames are treated as this long instead of
+/// catching up.
+const MAX_FRAME: u32 = 100;
+
+/// Rockets launched from the bottom of the cube that burst into coloured sparks.
+#[derive(Clone, Debug)]
+pub struct Fireworks {
+    /// Average time between rocket launches, ms.
+    launch_interval: u32,
+
+    /// Number of sparks in each burst.
+    spark_count: usize,
+
+    /// Speed of the sparks at the start of a burst, voxels per second.
+    burst_speed: f32,
+
+    sparks: ParticleSystem<MAX_SPARKS>,
+
+    rocket: Option<Particle>,
+
+    /// Number of rockets launched.
+    launches: u32,
+
+    next_launch: u32,
+
+    last_update: u32,
+
+    rng: SmallRng,
+}
+
+impl Default for Fireworks {
+    fn default() -> Self {
+        Self {
+            launch_interval: 1500,
+            spark_count: 16,
+            burst_speed: 2.5,
+            sparks: ParticleSystem::new(Vec3::new(0.0, 0.0, -4.0)),
+            rocket: None,
+            launches: 0,
+            next_launch: 0,
+            last_update: 0,
+            rng: SmallRng::seed_from_u64(Self::SEED),
+        }
+    }
+}
+
+impl Fireworks {
+    /// Set the average time between rocket launches in ms.
+    pub fn with_launch_interval(mut self, launch_interval: u32) -> Self {
+        self.launch_interval = launch_interval.max(1);
+
+        self
+    }
+
+    /// Set the number of sparks in each burst, up to 24.
+    pub fn with_spark_count(mut self, spark_count: usize) -> Self {
+        self.spark_count = spark_count.min(MAX_SPARKS);
+
+        self
+    }
+
+    /// Set the speed sparks fly out at in voxels per second.
+    pub fn with_burst_speed(mut self, burst_speed: f32) -> Self {
+        self.burst_speed = burst_speed;
+
+        self
+    }
+
+    /// Set the acceleration applied to the sparks in voxels per second squared.
+    pub fn with_gravity(mut self, gravity: Vec3) -> Self {
+        self.sparks.gravity = gravity;
+
+        self
+    }
+
+    fn launch(&mut self) {
+        let emitter = Emitter {
+            position: Vec3::new(
+                self.rng.gen_range(0.5, 2.5),
+                self.rng.gen_range(0.5, 2.5),
+                0.0,
+            ),
+            velocity: Vec3::new(0.0, 0.0, self.rng.gen_range(4.0, 5.5)),
+            spread: 0.3,
+            lifetime: 2000,
+            colour: WARM_WHITE,
+            rate: 0.0,
+        };
+
+        self.rocket = Some(emitter.particle(&mut self.rng));
+        self.launches += 1;
+    }
+
+    fn burst(&mut self, position: Vec3) {
+        let emitter = Emitter {
+            position,
+            velocity: Vec3::ZERO,
+            spread: self.burst_speed,
+            lifetime: self.rng.gen_range(700, 1100),
+            colour: Apa106Led::from_hsv(self.rng.gen(), 1.0, 1.0),
+            rate: 0.0,
+        };
+
+        self.sparks.emit(&emitter, self.spark_count, &mut self.rng);
+    }
+}
+
+impl Seeded for Fireworks {
+    const SEED: u64 = 0xf1de_f1de_f1de_f1de;
+
+    fn set_seed(&mut self, seed: u64) {
+        self.rng = SmallRng::seed_from_u64(seed);
+    }
+}
+
+impl PatternUpdate for Fireworks {
+    fn update(&mut self, time: u32) {
+        // Stand still if time goes backwards
+        let dt = time.saturating_sub(self.last_update).min(MAX_FRAME);
+        self.last_update = time;
+
+        // Jumped back in time, so launch when time catches up
+        if self.next_launch > time.saturating_add(self.launch_interval.saturating_mul(2)) {
+            self.next_launch = time;
+        }
+
+        if self.rocket.is_none() && time >= self.next_launch {
+            self.launch();
+
+            let jitter = self.launch_interval / 2;
+
+            self.next_launch = time
+                .saturating_add(self.launch_interval - jitter)
+                .saturating_add(self.rng.gen_range(0, jitter * 2 + 1));
+        }
+
+        let gravity = self.sparks.gravity;
+
+        if let Some(mut rocket) = self.rocket {
+            rocket.step(gravity, dt);
+
+            // Burst at the top of the climb or the top of the cube, whichever comes first
+            if rocket.velocity.z <= 0.0 || rocket.position.z >= 3.0 || !rocket.is_alive() {
+                self.rocket = None;
+
+                self.burst(rocket.position);
+            } else {
+                self.rocket = Some(rocket);
+            }
+        }
+
+        self.sparks.update(dt);
+    }
+
+    fn pixel_at(&self, idx: usize, _time: u32) -> Apa106Led {
+        let rocket = self
+            .rocket
+            .and_then(|rocket| rocket.position.to_voxel())
+            .map(|voxel| voxel.into_index_unchecked());
+
+        if rocket == Some(idx) {
+            WARM_WHITE
+        } else {
+            self.sparks.pixel_at(idx)
+        }
+    }
+
+    fn completed_cycles(&self, _time: u32) -> u32 {
+        // One cycle is one rocket
+        self.launches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn launches_and_bursts() {
+        let mut fireworks = Fireworks::default();
+
+        fireworks.update(0);
+
+        assert!(fireworks.rocket.is_some());
+
+        let mut burst = false;
+
+        for time in (0..3000).step_by(16) {
+            fireworks.update(time);
+
+            burst |= !fireworks.sparks.is_empty();
+        }
+
+        assert!(burst);
+        assert!(fireworks.completed_cycles(3000) >= 2);
+    }
+
+    #[test]
+    fn long_interval_and_late_time() {
+        let mut fireworks = Fireworks::default().with_launch_interval(u32::MAX);
+
+        fireworks.update(u32::MAX - 10);
+
+        assert!(fireworks.rocket.is_some());
+        assert_eq!(fireworks.next_launch, u32::MAX);
+
+        fireworks.update(u32::MAX);
+    }
+}
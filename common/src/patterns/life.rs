@@ -0,0 +1,378 @@
+use crate::{
+    apa106led::{Apa106Led, OFF},
+    clock::FixedStep,
+    palette::Palette,
+    patterns::{PatternUpdate, Seeded},
+    voxel::{Connectivity, Edges, Voxel},
+};
+use rand::prelude::*;
+
+/// Number of previous generations checked when looking for a repeating state.
+const HISTORY_LEN: usize = 8;
+
+/// Largest number of simulation steps run in one frame. Jumps further forward than this skip
+/// ahead instead of catching up.
+const MAX_STEPS: u32 = 16;
+
+/// Colour of a cell by age, from newly born to [`MAX_AGE`] generations old.
+const AGE_PALETTE: Palette = Palette::new(&[
+    Apa106Led {
+        red: 255,
+        green: 255,
+        blue: 255,
+    },
+    Apa106Led {
+        red: 0,
+        green: 255,
+        blue: 80,
+    },
+    Apa106Led {
+        red: 0,
+        green: 80,
+        blue: 255,
+    },
+    Apa106Led {
+        red: 120,
+        green: 0,
+        blue: 160,
+    },
+]);
+
+/// Age at which a cell reaches the end of the palette.
+const MAX_AGE: u8 = 12;
+
+/// Error produced when parsing a [`Rule`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RuleError {
+    /// The rule isn't in `B<counts>/S<counts>` form.
+    Syntax,
+
+    /// A neighbour count is larger than the 26 neighbours a voxel can have.
+    CountOutOfRange(u8),
+}
+
+/// Birth and survival conditions for a Life-like automaton.
+///
+/// Each field is a bitmask where bit `n` is set if a cell with `n` live neighbours is born or
+/// survives respectively.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub birth: u32,
+    pub survival: u32,
+}
+
+impl Rule {
+    /// `B5/S45`, a slow, stable 3D rule.
+    pub const B5_S45: Rule = Rule::new(1 << 5, 1 << 4 | 1 << 5);
+
+    /// `B4/S34`, a busier rule that suits the small bounded grid.
+    pub const B4_S34: Rule = Rule::new(1 << 4, 1 << 3 | 1 << 4);
+
+    pub const fn new(birth: u32, survival: u32) -> Self {
+        Self { birth, survival }
+    }
+
+    /// Parse a rule in birth/survival notation, e.g. `B5/S45`.
+    ///
+    /// Counts are either a run of single digits (`S45` is 4 or 5 neighbours), or a comma
+    /// separated list of numbers and inclusive ranges for counts above 9 (`S4-6,12`).
+    pub fn parse(rule: &str) -> Result<Self, RuleError> {
+        let mut parts = rule.trim().split('/');
+
+        let birth = parts.next().ok_or(RuleError::Syntax)?;
+        let survival = parts.next().ok_or(RuleError::Syntax)?;
+
+        if parts.next().is_some() {
+            return Err(RuleError::Syntax);
+        }
+
+        let birth = strip_prefix(birth, 'B').ok_or(RuleError::Syntax)?;
+        let survival = strip_prefix(survival, 'S').ok_or(RuleError::Syntax)?;
+
+        Ok(Self {
+            birth: parse_counts(birth)?,
+            survival: parse_counts(survival)?,
+        })
+    }
+
+    fn is_born(&self, neighbours: u8) -> bool {
+        self.birth & (1 << neighbours) != 0
+    }
+
+    fn survives(&self, neighbours: u8) -> bool {
+        self.survival & (1 << neighbours) != 0
+    }
+}
+
+fn strip_prefix(part: &str, prefix: char) -> Option<&str> {
+    part.strip_prefix(prefix)
+        .or_else(|| part.strip_prefix(prefix.to_ascii_lowercase()))
+}
+
+fn parse_count(count: &str) -> Result<u8, RuleError> {
+    let count = count.parse::<u8>().map_err(|_| RuleError::Syntax)?;
+
+    if count > 26 {
+        Err(RuleError::CountOutOfRange(count))
+    } else {
+        Ok(count)
+    }
+}
+
+fn parse_counts(counts: &str) -> Result<u32, RuleError> {
+    let mut mask = 0;
+
+    if counts.contains([',', '-']) {
+        for item in counts.split(',') {
+            let mut range = item.split('-');
+
+            let start = parse_count(range.next().ok_or(RuleError::Syntax)?)?;
+            let end = match range.next() {
+                Some(end) => parse_count(end)?,
+                None => start,
+            };
+
+            if range.next().is_some() || end < start {
+                return Err(RuleError::Syntax);
+            }
+
+            for count in start..=end {
+                mask |= 1 << count;
+            }
+        }
+    } else {
+        for c in counts.chars() {
+            let count = c.to_digit(10).ok_or(RuleError::Syntax)?;
+
+            mask |= 1 << count;
+        }
+    }
+
+    Ok(mask)
+}
+
+/// Life-like 3D cellular automaton.
+///
+/// Cells are coloured by how many generations they've been alive for. The grid is reseeded with
+/// random cells when everything dies or the state starts repeating.
+#[derive(Clone, Debug)]
+pub struct Life {
+    rule: Rule,
+
+    connectivity: Connectivity,
+
+    edges: Edges,
+
+    /// Chance out of 64 that a cell is alive after reseeding.
+    density: u8,
+
+    palette: Palette,
+
+    /// Age in generations of each cell by frame index, or 0 for dead cells.
+    ages: [u8; 64],
+
+    /// Live cell bitmasks of previous generations, used to detect repeating states.
+    history: [u64; HISTORY_LEN],
+
+    fixed_step: FixedStep,
+
+    rng: SmallRng,
+}
+
+impl Default for Life {
+    fn default() -> Self {
+        let mut pattern = Self {
+            rule: Rule::B4_S34,
+            connectivity: Connectivity::TwentySix,
+            edges: Edges::Bounded,
+            density: 20,
+            palette: AGE_PALETTE,
+            ages: [0; 64],
+            history: [0; HISTORY_LEN],
+            fixed_step: FixedStep::new(400, MAX_STEPS),
+            rng: SmallRng::seed_from_u64(Self::SEED),
+        };
+
+        pattern.reseed();
+
+        pattern
+    }
+}
+
+impl Life {
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.rule = rule;
+
+        self
+    }
+
+    pub fn with_connectivity(mut self, connectivity: Connectivity) -> Self {
+        self.connectivity = connectivity;
+
+        self
+    }
+
+    /// Set whether cells on the faces of the cube see the opposite face as neighbours.
+    pub fn with_edges(mut self, edges: Edges) -> Self {
+        self.edges = edges;
+
+        self
+    }
+
+    /// Set the time between generations in ms.
+    pub fn with_step_time(mut self, step_time: u32) -> Self {
+        self.fixed_step.set_step_time(step_time);
+
+        self
+    }
+
+    /// Set the chance out of 64 that each cell is alive when the grid is reseeded, from 1 to 64.
+    pub fn with_density(mut self, density: u8) -> Self {
+        self.density = density.clamp(1, 64);
+
+        self.reseed();
+
+        self
+    }
+
+    /// Set the colours cells pass through as they age.
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+
+        self
+    }
+
+    fn alive_mask(&self) -> u64 {
+        self.ages
+            .iter()
+            .enumerate()
+            .filter(|(_, age)| **age > 0)
+            .fold(0, |mask, (idx, _)| mask | 1 << idx)
+    }
+
+    /// Fill the grid with new random cells.
+    fn reseed(&mut self) {
+        let density = self.density as u32;
+        let rng = &mut self.rng;
+
+        self.ages
+            .iter_mut()
+            .for_each(|age| *age = if rng.next_u32() % 64 < density { 1 } else { 0 });
+
+        self.history = [0; HISTORY_LEN];
+    }
+
+    /// Advance one generation.
+    fn step(&mut self) {
+        let mut next = [0u8; 64];
+
+        for (idx, cell) in next.iter_mut().enumerate() {
+            let neighbours = Voxel::from_index_unchecked(idx)
+                .neighbours(self.connectivity, self.edges)
+                .filter(|n| self.ages[n.into_index_unchecked()] > 0)
+                .count() as u8;
+
+            let age = self.ages[idx];
+
+            *cell = if age > 0 && self.rule.survives(neighbours) {
+                age.saturating_add(1)
+            } else if age == 0 && self.rule.is_born(neighbours) {
+                1
+            } else {
+                0
+            };
+        }
+
+        self.ages = next;
+
+        let mask = self.alive_mask();
+
+        if mask == 0 || self.history.contains(&mask) {
+            self.reseed();
+        } else {
+            self.history.rotate_right(1);
+            self.history[0] = mask;
+        }
+    }
+}
+
+impl Seeded for Life {
+    const SEED: u64 = 0x11fe_11fe_11fe_11fe;
+
+    fn set_seed(&mut self, seed: u64) {
+        self.rng = SmallRng::seed_from_u64(seed);
+
+        self.reseed();
+    }
+}
+
+impl PatternUpdate for Life {
+    fn update(&mut self, time: u32) {
+        for _ in self.fixed_step.steps(time) {
+            self.step();
+        }
+    }
+
+    fn pixel_at(&self, idx: usize, _time: u32) -> Apa106Led {
+        match self.ages[idx] {
+            0 => OFF,
+            age => self.palette.sample((age - 1) as f32 / (MAX_AGE - 1) as f32),
+        }
+    }
+
+    fn cycle_length(&self) -> Option<u32> {
+        // One cycle is 16 generations
+        Some(self.fixed_step.step_time().saturating_mul(16))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rules() {
+        assert_eq!(Rule::parse("B5/S45"), Ok(Rule::B5_S45));
+        assert_eq!(Rule::parse("b4/s34"), Ok(Rule::B4_S34));
+        assert_eq!(
+            Rule::parse("B4-6,13/S"),
+            Ok(Rule::new(1 << 4 | 1 << 5 | 1 << 6 | 1 << 13, 0))
+        );
+        assert_eq!(Rule::parse("B5"), Err(RuleError::Syntax));
+        assert_eq!(Rule::parse("S5/B4"), Err(RuleError::Syntax));
+        assert_eq!(Rule::parse("B5,27/S1"), Err(RuleError::CountOutOfRange(27)));
+    }
+
+    #[test]
+    fn birth_and_death() {
+        let mut life = Life::default()
+            .with_rule(Rule::parse("B1/S").unwrap())
+            .with_connectivity(Connectivity::Six)
+            .with_edges(Edges::Bounded);
+
+        life.ages = [0; 64];
+        life.ages[0] = 1;
+        life.history = [0; HISTORY_LEN];
+
+        life.step();
+
+        let expected = [
+            Voxel { x: 1, y: 0, z: 0 },
+            Voxel { x: 0, y: 1, z: 0 },
+            Voxel { x: 0, y: 0, z: 1 },
+        ]
+        .iter()
+        .fold(0, |mask, voxel| mask | 1 << voxel.into_index_unchecked());
+
+        assert_eq!(life.alive_mask(), expected);
+    }
+
+    #[test]
+    fn reseeds_when_dead() {
+        let mut life = Life::default().with_rule(Rule::new(0, 0));
+
+        life.step();
+
+        assert_ne!(life.alive_mask(), 0);
+    }
+}
@@ -1,14 +1,43 @@
+mod animated_slices;
+mod blender;
+mod bouncing_balls;
 mod christmas_puke;
+mod fire;
+mod fireflies;
+mod fireworks;
+mod life;
+mod plasma;
+mod rain;
 mod rainbow;
+pub mod registry;
+mod ripples;
+mod scripted;
 mod slices;
 mod slow_rain;
+mod snake;
+mod sprite;
+mod wave;
 
-use crate::apa106led::Apa106Led;
+use crate::{apa106led::Apa106Led, input::InputEvent};
+pub use animated_slices::*;
+pub use blender::*;
+pub use bouncing_balls::*;
 pub use christmas_puke::*;
 use core::iter::Iterator;
+pub use fire::*;
+pub use fireflies::*;
+pub use fireworks::*;
+pub use life::*;
+pub use plasma::*;
+pub use rain::*;
 pub use rainbow::*;
+pub use ripples::*;
+pub use scripted::*;
 pub use slices::*;
 pub use slow_rain::*;
+pub use snake::*;
+pub use sprite::*;
+pub use wave::*;
 
 #[derive(Clone, Debug)]
 pub enum Pattern {
@@ -16,10 +45,53 @@ pub enum Pattern {
     SlowRain(SlowRain),
     ChristmasPuke(ChristmasPuke),
     Slices(Slices),
+    Fireflies(Fireflies),
+    Life(Life),
+    Plasma(Plasma),
+    Fire(Fire),
+    Snake(Snake),
+    BouncingBalls(BouncingBalls),
+    Blender(Blender),
+    AnimatedSlices(AnimatedSlices),
+    Rain(Rain),
+    Wave(Wave),
+    Ripples(Ripples),
+    Fireworks(Fireworks),
+    Sprite(Sprite),
+    Scripted(Scripted),
 }
 
 impl Pattern {
+    /// Seed the random number generator of patterns that use one, so they play out the same way
+    /// every time for a given seed. Patterns that don't use randomness are returned unchanged.
+    pub fn with_seed(self, seed: u64) -> Self {
+        match self {
+            Self::SlowRain(p) => Self::SlowRain(p.with_seed(seed)),
+            Self::Fireflies(p) => Self::Fireflies(p.with_seed(seed)),
+            Self::Life(p) => Self::Life(p.with_seed(seed)),
+            Self::Fire(p) => Self::Fire(p.with_seed(seed)),
+            Self::Snake(p) => Self::Snake(p.with_seed(seed)),
+            Self::BouncingBalls(p) => Self::BouncingBalls(p.with_seed(seed)),
+            Self::Rain(p) => Self::Rain(p.with_seed(seed)),
+            Self::Ripples(p) => Self::Ripples(p.with_seed(seed)),
+            Self::Fireworks(p) => Self::Fireworks(p.with_seed(seed)),
+            Self::Rainbow(_)
+            | Self::ChristmasPuke(_)
+            | Self::Slices(_)
+            | Self::Plasma(_)
+            | Self::Blender(_)
+            | Self::AnimatedSlices(_)
+            | Self::Wave(_)
+            | Self::Sprite(_)
+            | Self::Scripted(_) => self,
+        }
+    }
+
+    /// Run the pattern's per-frame [`PatternUpdate::update`] step, then iterate over every pixel
+    /// in the frame.
     pub fn update_iter(&'_ mut self, time: u32) -> PatternIter<'_> {
+        self.update(time);
+
         PatternIter {
             pattern: self,
             idx: 0,
@@ -28,20 +100,315 @@ impl Pattern {
     }
 }
 
+impl PatternUpdate for Pattern {
+    fn enter(&mut self) {
+        match self {
+            Self::Rainbow(p) => p.enter(),
+            Self::SlowRain(p) => p.enter(),
+            Self::ChristmasPuke(p) => p.enter(),
+            Self::Slices(p) => p.enter(),
+            Self::Fireflies(p) => p.enter(),
+            Self::Life(p) => p.enter(),
+            Self::Plasma(p) => p.enter(),
+            Self::Fire(p) => p.enter(),
+            Self::Snake(p) => p.enter(),
+            Self::BouncingBalls(p) => p.enter(),
+            Self::Blender(p) => p.enter(),
+            Self::AnimatedSlices(p) => p.enter(),
+            Self::Rain(p) => p.enter(),
+            Self::Wave(p) => p.enter(),
+            Self::Ripples(p) => p.enter(),
+            Self::Fireworks(p) => p.enter(),
+            Self::Sprite(p) => p.enter(),
+            Self::Scripted(p) => p.enter(),
+        }
+    }
+
+    fn update(&mut self, time: u32) {
+        match self {
+            Self::Rainbow(p) => p.update(time),
+            Self::SlowRain(p) => p.update(time),
+            Self::ChristmasPuke(p) => p.update(time),
+            Self::Slices(p) => p.update(time),
+            Self::Fireflies(p) => p.update(time),
+            Self::Life(p) => p.update(time),
+            Self::Plasma(p) => p.update(time),
+            Self::Fire(p) => p.update(time),
+            Self::Snake(p) => p.update(time),
+            Self::BouncingBalls(p) => p.update(time),
+            Self::Blender(p) => p.update(time),
+            Self::AnimatedSlices(p) => p.update(time),
+            Self::Rain(p) => p.update(time),
+            Self::Wave(p) => p.update(time),
+            Self::Ripples(p) => p.update(time),
+            Self::Fireworks(p) => p.update(time),
+            Self::Sprite(p) => p.update(time),
+            Self::Scripted(p) => p.update(time),
+        }
+    }
+
+    fn pixel_at(&self, idx: usize, time: u32) -> Apa106Led {
+        match self {
+            Self::Rainbow(p) => p.pixel_at(idx, time),
+            Self::SlowRain(p) => p.pixel_at(idx, time),
+            Self::ChristmasPuke(p) => p.pixel_at(idx, time),
+            Self::Slices(p) => p.pixel_at(idx, time),
+            Self::Fireflies(p) => p.pixel_at(idx, time),
+            Self::Life(p) => p.pixel_at(idx, time),
+            Self::Plasma(p) => p.pixel_at(idx, time),
+            Self::Fire(p) => p.pixel_at(idx, time),
+            Self::Snake(p) => p.pixel_at(idx, time),
+            Self::BouncingBalls(p) => p.pixel_at(idx, time),
+            Self::Blender(p) => p.pixel_at(idx, time),
+            Self::AnimatedSlices(p) => p.pixel_at(idx, time),
+            Self::Rain(p) => p.pixel_at(idx, time),
+            Self::Wave(p) => p.pixel_at(idx, time),
+            Self::Ripples(p) => p.pixel_at(idx, time),
+            Self::Fireworks(p) => p.pixel_at(idx, time),
+            Self::Sprite(p) => p.pixel_at(idx, time),
+            Self::Scripted(p) => p.pixel_at(idx, time),
+        }
+    }
+
+    fn exit(&mut self) {
+        match self {
+            Self::Rainbow(p) => p.exit(),
+            Self::SlowRain(p) => p.exit(),
+            Self::ChristmasPuke(p) => p.exit(),
+            Self::Slices(p) => p.exit(),
+            Self::Fireflies(p) => p.exit(),
+            Self::Life(p) => p.exit(),
+            Self::Plasma(p) => p.exit(),
+            Self::Fire(p) => p.exit(),
+            Self::Snake(p) => p.exit(),
+            Self::BouncingBalls(p) => p.exit(),
+            Self::Blender(p) => p.exit(),
+            Self::AnimatedSlices(p) => p.exit(),
+            Self::Rain(p) => p.exit(),
+            Self::Wave(p) => p.exit(),
+            Self::Ripples(p) => p.exit(),
+            Self::Fireworks(p) => p.exit(),
+            Self::Sprite(p) => p.exit(),
+            Self::Scripted(p) => p.exit(),
+        }
+    }
+
+    fn input(&mut self, event: InputEvent) {
+        match self {
+            Self::Rainbow(p) => p.input(event),
+            Self::SlowRain(p) => p.input(event),
+            Self::ChristmasPuke(p) => p.input(event),
+            Self::Slices(p) => p.input(event),
+            Self::Fireflies(p) => p.input(event),
+            Self::Life(p) => p.input(event),
+            Self::Plasma(p) => p.input(event),
+            Self::Fire(p) => p.input(event),
+            Self::Snake(p) => p.input(event),
+            Self::BouncingBalls(p) => p.input(event),
+            Self::Blender(p) => p.input(event),
+            Self::AnimatedSlices(p) => p.input(event),
+            Self::Rain(p) => p.input(event),
+            Self::Wave(p) => p.input(event),
+            Self::Ripples(p) => p.input(event),
+            Self::Fireworks(p) => p.input(event),
+            Self::Sprite(p) => p.input(event),
+            Self::Scripted(p) => p.input(event),
+        }
+    }
+
+    fn wind_down(&mut self, time: u32) {
+        match self {
+            Self::Rainbow(p) => p.wind_down(time),
+            Self::SlowRain(p) => p.wind_down(time),
+            Self::ChristmasPuke(p) => p.wind_down(time),
+            Self::Slices(p) => p.wind_down(time),
+            Self::Fireflies(p) => p.wind_down(time),
+            Self::Life(p) => p.wind_down(time),
+            Self::Plasma(p) => p.wind_down(time),
+            Self::Fire(p) => p.wind_down(time),
+            Self::Snake(p) => p.wind_down(time),
+            Self::BouncingBalls(p) => p.wind_down(time),
+            Self::Blender(p) => p.wind_down(time),
+            Self::AnimatedSlices(p) => p.wind_down(time),
+            Self::Rain(p) => p.wind_down(time),
+            Self::Wave(p) => p.wind_down(time),
+            Self::Ripples(p) => p.wind_down(time),
+            Self::Fireworks(p) => p.wind_down(time),
+            Self::Sprite(p) => p.wind_down(time),
+            Self::Scripted(p) => p.wind_down(time),
+        }
+    }
+
+    fn is_finished(&self, time: u32) -> bool {
+        match self {
+            Self::Rainbow(p) => p.is_finished(time),
+            Self::SlowRain(p) => p.is_finished(time),
+            Self::ChristmasPuke(p) => p.is_finished(time),
+            Self::Slices(p) => p.is_finished(time),
+            Self::Fireflies(p) => p.is_finished(time),
+            Self::Life(p) => p.is_finished(time),
+            Self::Plasma(p) => p.is_finished(time),
+            Self::Fire(p) => p.is_finished(time),
+            Self::Snake(p) => p.is_finished(time),
+            Self::BouncingBalls(p) => p.is_finished(time),
+            Self::Blender(p) => p.is_finished(time),
+            Self::AnimatedSlices(p) => p.is_finished(time),
+            Self::Rain(p) => p.is_finished(time),
+            Self::Wave(p) => p.is_finished(time),
+            Self::Ripples(p) => p.is_finished(time),
+            Self::Fireworks(p) => p.is_finished(time),
+            Self::Sprite(p) => p.is_finished(time),
+            Self::Scripted(p) => p.is_finished(time),
+        }
+    }
+
+    fn has_natural_end(&self) -> bool {
+        match self {
+            Self::Rainbow(p) => p.has_natural_end(),
+            Self::SlowRain(p) => p.has_natural_end(),
+            Self::ChristmasPuke(p) => p.has_natural_end(),
+            Self::Slices(p) => p.has_natural_end(),
+            Self::Fireflies(p) => p.has_natural_end(),
+            Self::Life(p) => p.has_natural_end(),
+            Self::Plasma(p) => p.has_natural_end(),
+            Self::Fire(p) => p.has_natural_end(),
+            Self::Snake(p) => p.has_natural_end(),
+            Self::BouncingBalls(p) => p.has_natural_end(),
+            Self::Blender(p) => p.has_natural_end(),
+            Self::AnimatedSlices(p) => p.has_natural_end(),
+            Self::Rain(p) => p.has_natural_end(),
+            Self::Wave(p) => p.has_natural_end(),
+            Self::Ripples(p) => p.has_natural_end(),
+            Self::Fireworks(p) => p.has_natural_end(),
+            Self::Sprite(p) => p.has_natural_end(),
+            Self::Scripted(p) => p.has_natural_end(),
+        }
+    }
+
+    fn cycle_length(&self) -> Option<u32> {
+        match self {
+            Self::Rainbow(p) => p.cycle_length(),
+            Self::SlowRain(p) => p.cycle_length(),
+            Self::ChristmasPuke(p) => p.cycle_length(),
+            Self::Slices(p) => p.cycle_length(),
+            Self::Fireflies(p) => p.cycle_length(),
+            Self::Life(p) => p.cycle_length(),
+            Self::Plasma(p) => p.cycle_length(),
+            Self::Fire(p) => p.cycle_length(),
+            Self::Snake(p) => p.cycle_length(),
+            Self::BouncingBalls(p) => p.cycle_length(),
+            Self::Blender(p) => p.cycle_length(),
+            Self::AnimatedSlices(p) => p.cycle_length(),
+            Self::Rain(p) => p.cycle_length(),
+            Self::Wave(p) => p.cycle_length(),
+            Self::Ripples(p) => p.cycle_length(),
+            Self::Fireworks(p) => p.cycle_length(),
+            Self::Sprite(p) => p.cycle_length(),
+            Self::Scripted(p) => p.cycle_length(),
+        }
+    }
+
+    fn completed_cycles(&self, time: u32) -> u32 {
+        match self {
+            Self::Rainbow(p) => p.completed_cycles(time),
+            Self::SlowRain(p) => p.completed_cycles(time),
+            Self::ChristmasPuke(p) => p.completed_cycles(time),
+            Self::Slices(p) => p.completed_cycles(time),
+            Self::Fireflies(p) => p.completed_cycles(time),
+            Self::Life(p) => p.completed_cycles(time),
+            Self::Plasma(p) => p.completed_cycles(time),
+            Self::Fire(p) => p.completed_cycles(time),
+            Self::Snake(p) => p.completed_cycles(time),
+            Self::BouncingBalls(p) => p.completed_cycles(time),
+            Self::Blender(p) => p.completed_cycles(time),
+            Self::AnimatedSlices(p) => p.completed_cycles(time),
+            Self::Rain(p) => p.completed_cycles(time),
+            Self::Wave(p) => p.completed_cycles(time),
+            Self::Ripples(p) => p.completed_cycles(time),
+            Self::Fireworks(p) => p.completed_cycles(time),
+            Self::Sprite(p) => p.completed_cycles(time),
+            Self::Scripted(p) => p.completed_cycles(time),
+        }
+    }
+}
+
 pub trait PatternUpdate {
-    type CycleCounter;
+    /// Called once when the pattern becomes active, before its first frame.
+    fn enter(&mut self) {}
+
+    /// Advance any per-frame state. Called once per frame before the frame's pixels are sampled
+    /// with [`PatternUpdate::pixel_at`].
+    fn update(&mut self, _time: u32) {}
+
+    fn pixel_at(&self, idx: usize, time: u32) -> Apa106Led;
+
+    /// Called once when the pattern is replaced by another.
+    fn exit(&mut self) {}
 
-    fn pixel_at(&mut self, idx: usize, time: u32) -> Apa106Led;
+    /// React to input from the user, such as a button press. Called between frames, so any effect
+    /// is shown from the next [`update`](PatternUpdate::update).
+    fn input(&mut self, _event: InputEvent) {}
 
-    /// Get number of complete cycles this pattern will have run at a certain time.
+    /// Ask the pattern to end gracefully, for example by no longer lighting new voxels and letting
+    /// existing ones fade out. Only has an effect on patterns with a
+    /// [natural end](PatternUpdate::has_natural_end).
+    fn wind_down(&mut self, _time: u32) {}
+
+    /// Whether the pattern has come to an end by itself and can be replaced without cutting it
+    /// off, for example after being asked to [`wind_down`](PatternUpdate::wind_down).
     ///
-    /// If the number is not known or cannot be computed, `None` should be returned.
-    fn completed_cycles(&self, time: u32) -> Self::CycleCounter;
+    /// Patterns without a natural end never finish.
+    fn is_finished(&self, _time: u32) -> bool {
+        false
+    }
+
+    /// Whether the pattern can end by itself. The playlist asks these patterns to
+    /// [`wind_down`](PatternUpdate::wind_down) and waits for them to
+    /// [finish](PatternUpdate::is_finished). Other patterns are replaced at the end of a cycle.
+    fn has_natural_end(&self) -> bool {
+        false
+    }
+
+    /// Length of one natural cycle of the pattern in ms, for example one sweep through the
+    /// rainbow, or `None` if the pattern has no fixed cycle length.
+    fn cycle_length(&self) -> Option<u32> {
+        None
+    }
+
+    /// Number of complete cycles the pattern has run by `time`.
+    ///
+    /// Counts whole [`cycle_length`](PatternUpdate::cycle_length)s by default. Patterns whose
+    /// cycles aren't a fixed length, like games of Snake, count them themselves. Patterns with
+    /// neither never complete a cycle.
+    fn completed_cycles(&self, time: u32) -> u32 {
+        match self.cycle_length() {
+            Some(length) => time / length.max(1),
+            None => 0,
+        }
+    }
+}
+
+/// Patterns that use a random number generator.
+///
+/// Each pattern starts from its own fixed [`SEED`](Seeded::SEED), so it plays out the same way
+/// every run unless given a different seed.
+pub trait Seeded: Sized {
+    /// Seed used by default.
+    const SEED: u64;
+
+    /// Reseed the random number generator and regenerate any random starting state.
+    fn set_seed(&mut self, seed: u64);
+
+    fn with_seed(mut self, seed: u64) -> Self {
+        self.set_seed(seed);
+
+        self
+    }
 }
 
 /// Iterator over all voxels in a frame, used to update the cube display buffer.
 pub struct PatternIter<'a> {
-    pattern: &'a mut Pattern,
+    pattern: &'a Pattern,
     idx: usize,
     time: u32,
 }
@@ -54,12 +421,7 @@ impl<'a> Iterator for PatternIter<'a> {
             return None;
         }
 
-        let pixel = match self.pattern {
-            Pattern::Rainbow(p) => p.pixel_at(self.idx, self.time),
-            Pattern::SlowRain(p) => p.pixel_at(self.idx, self.time),
-            Pattern::ChristmasPuke(p) => p.pixel_at(self.idx, self.time),
-            Pattern::Slices(p) => p.pixel_at(self.idx, self.time),
-        };
+        let pixel = self.pattern.pixel_at(self.idx, self.time);
 
         self.idx += 1;
 
@@ -0,0 +1,72 @@
+use crate::{
+    apa106led::Apa106Led, noise, palette::Palette, patterns::PatternUpdate, vec3::Vec3,
+    voxel::Voxel,
+};
+
+/// Slowly drifting blobs of colour, made by sampling a 3D noise field that changes over time.
+#[derive(Clone, Debug)]
+pub struct Plasma {
+    /// Noise units per voxel. Smaller values give larger, smoother blobs.
+    scale: f32,
+
+    /// Noise units travelled through time per second.
+    speed: f32,
+
+    /// Layers of finer detail added to the noise.
+    octaves: u8,
+
+    palette: Palette,
+}
+
+impl Default for Plasma {
+    fn default() -> Self {
+        Self {
+            scale: 0.3,
+            speed: 0.25,
+            octaves: 2,
+            palette: Palette::OCEAN,
+        }
+    }
+}
+
+impl Plasma {
+    /// Set the noise units per voxel. Smaller values give larger, smoother blobs.
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+
+        self
+    }
+
+    /// Set how quickly the pattern changes, in noise units per second.
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+
+        self
+    }
+
+    /// Set the number of layers of finer detail, from 1 upwards.
+    pub fn with_octaves(mut self, octaves: u8) -> Self {
+        self.octaves = octaves.max(1);
+
+        self
+    }
+
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+
+        self
+    }
+}
+
+impl PatternUpdate for Plasma {
+    fn pixel_at(&self, idx: usize, time: u32) -> Apa106Led {
+        let pos = Vec3::from(Voxel::from_index_unchecked(idx)) * self.scale;
+
+        let t = time as f32 / 1000.0 * self.speed;
+
+        let value = noise::fbm(pos.x, pos.y, pos.z, t, self.octaves);
+
+        // Noise rarely strays far from zero, so stretch it to use more of the palette
+        self.palette.sample(0.5 + value * 1.5)
+    }
+}
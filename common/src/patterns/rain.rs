@@ -0,0 +1,153 @@
+use crate::{
+    apa106led::{Apa106Led, OFF, WARM_WHITE},
+    clock::FixedStep,
+    patterns::{PatternUpdate, Seeded},
+    voxel::Voxel,
+};
+use rand::prelude::*;
+
+/// Each cycle spawns a row of drops then moves them down 4 times.
+const CYCLE_STEPS: u32 = 5;
+
+/// Largest number of simulation steps run in one frame. Jumps further forward than this skip
+/// ahead instead of catching up.
+const MAX_STEPS: u32 = 16;
+
+/// Drops that spawn in the top layer and fall one layer at a time.
+#[derive(Clone, Debug)]
+pub struct Rain {
+    colour: Apa106Led,
+
+    /// Chance out of 64 that a drop spawns at each position in the top layer.
+    density: u8,
+
+    /// Drops in each layer from the bottom up. Bit `x + y * 4` is set if there's a drop at that
+    /// position.
+    layers: [u16; 4],
+
+    /// Number of steps run.
+    steps: u32,
+
+    fixed_step: FixedStep,
+
+    rng: SmallRng,
+}
+
+impl Default for Rain {
+    fn default() -> Self {
+        Self {
+            colour: WARM_WHITE,
+            density: 16,
+            layers: [0; 4],
+            steps: 0,
+            fixed_step: FixedStep::new(120, MAX_STEPS),
+            rng: SmallRng::seed_from_u64(Self::SEED),
+        }
+    }
+}
+
+impl Rain {
+    pub fn with_colour(mut self, colour: Apa106Led) -> Self {
+        self.colour = colour;
+
+        self
+    }
+
+    /// Set the time between steps in ms.
+    pub fn with_step_time(mut self, step_time: u32) -> Self {
+        self.fixed_step.set_step_time(step_time);
+
+        self
+    }
+
+    /// Set the chance out of 64 that a drop spawns at each position in the top layer.
+    pub fn with_density(mut self, density: u8) -> Self {
+        self.density = density.min(64);
+
+        self
+    }
+
+    fn step(&mut self) {
+        match self.steps % CYCLE_STEPS {
+            // New drops form in the top layer at the start of each cycle
+            0 => {
+                let density = self.density as u32;
+                let rng = &mut self.rng;
+
+                self.layers[3] = (0..16).fold(0, |layer, bit| {
+                    if rng.next_u32() % 64 < density {
+                        layer | 1 << bit
+                    } else {
+                        layer
+                    }
+                });
+            }
+            // Drops in the bottom layer fall out of the cube
+            _ => {
+                self.layers.rotate_left(1);
+                self.layers[3] = 0;
+            }
+        }
+
+        self.steps += 1;
+    }
+}
+
+impl Seeded for Rain {
+    const SEED: u64 = 0x4a14_4a14_4a14_4a14;
+
+    fn set_seed(&mut self, seed: u64) {
+        self.rng = SmallRng::seed_from_u64(seed);
+    }
+}
+
+impl PatternUpdate for Rain {
+    fn update(&mut self, time: u32) {
+        for _ in self.fixed_step.steps(time) {
+            self.step();
+        }
+    }
+
+    fn pixel_at(&self, idx: usize, _time: u32) -> Apa106Led {
+        let Voxel { x, y, z } = Voxel::from_index_unchecked(idx);
+
+        if self.layers[z as usize] & 1 << (x + y * 4) != 0 {
+            self.colour
+        } else {
+            OFF
+        }
+    }
+
+    fn cycle_length(&self) -> Option<u32> {
+        Some(self.fixed_step.step_time().saturating_mul(CYCLE_STEPS))
+    }
+
+    fn completed_cycles(&self, _time: u32) -> u32 {
+        // Count steps actually run, as slow frames can skip some
+        self.steps / CYCLE_STEPS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_fall() {
+        let mut rain = Rain::default().with_density(64);
+
+        rain.update(0);
+
+        assert_eq!(rain.layers, [0, 0, 0, 0xffff]);
+
+        rain.update(rain.fixed_step.step_time() * 3);
+
+        assert_eq!(rain.layers, [0xffff, 0, 0, 0]);
+        assert!((0..16).all(|idx| rain.pixel_at(idx, 0) == rain.colour));
+
+        rain.update(rain.fixed_step.step_time() * 4);
+
+        assert_eq!(rain.layers, [0; 4]);
+        assert_eq!(rain.completed_cycles(0), 1);
+    }
+}
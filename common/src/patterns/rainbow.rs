@@ -1,29 +1,53 @@
-use crate::{apa106led::Apa106Led, patterns::PatternUpdate};
+use crate::{apa106led::Apa106Led, palette::Palette, patterns::PatternUpdate};
 use core::f32::consts::PI;
+#[cfg(not(test))]
 use micromath::F32Ext;
 
 #[derive(Clone, Debug)]
 pub struct Rainbow {
+    /// Time for the colour wheel to make one full turn, ms.
     duration: u32,
+
+    /// Colours to cycle through instead of the full RGB colour wheel.
+    palette: Option<Palette>,
+}
+
+impl Rainbow {
+    /// Set the cycle time in ms. Shorter durations give a faster rainbow.
+    pub fn with_duration(mut self, duration: u32) -> Self {
+        self.duration = duration.max(1);
+
+        self
+    }
+
+    /// Cycle through the colours of a palette instead of the full RGB colour wheel.
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = Some(palette);
+
+        self
+    }
 }
 
 impl Default for Rainbow {
     fn default() -> Self {
-        Self { duration: 1000 }
+        Self {
+            duration: 1000,
+            palette: None,
+        }
     }
 }
 
 impl PatternUpdate for Rainbow {
-    type CycleCounter = u32;
-    // type Iter = RainbowIter;
-
-    fn pixel_at(&mut self, idx: usize, time: u32) -> Apa106Led {
+    fn pixel_at(&self, idx: usize, time: u32) -> Apa106Led {
         let step = idx as f32 / 64.0;
         let offset = step * PI;
 
-        // 1 second cycle time
         let t = time as f32 / (self.duration as f32 / PI);
 
+        if let Some(palette) = self.palette {
+            return palette.sample_wrapping((t + offset) / (2.0 * PI));
+        }
+
         let r = scale((t + offset).sin());
         let g = scale((t + offset + ((2.0 * PI) / 3.0)).sin());
         let b = scale((t + offset + ((4.0 * PI) / 3.0)).sin());
@@ -35,8 +59,8 @@ impl PatternUpdate for Rainbow {
         }
     }
 
-    fn completed_cycles(&self, time: u32) -> Self::CycleCounter {
-        time / self.duration
+    fn cycle_length(&self) -> Option<u32> {
+        Some(self.duration)
     }
 }
 
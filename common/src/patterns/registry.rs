@@ -0,0 +1,160 @@
+//! Runtime lookup of patterns by name or numeric ID.
+//!
+//! Names and IDs are stable so they can be stored in config files or sent as commands. Add new
+//! patterns to the end of [`PATTERNS`] with a new ID and never renumber existing entries.
+
+use crate::patterns::*;
+
+/// A pattern that can be selected at runtime.
+#[derive(Copy, Clone, Debug)]
+pub struct PatternEntry {
+    /// Stable numeric ID.
+    pub id: u8,
+
+    /// Stable kebab-case name.
+    pub name: &'static str,
+
+    constructor: fn() -> Pattern,
+}
+
+impl PatternEntry {
+    /// Create a new instance of this pattern with its default settings.
+    pub fn build(&self) -> Pattern {
+        (self.constructor)()
+    }
+}
+
+/// Every selectable pattern, in ID order.
+pub const PATTERNS: &[PatternEntry] = &[
+    PatternEntry {
+        id: 0,
+        name: "rainbow",
+        constructor: || Pattern::Rainbow(Rainbow::default()),
+    },
+    PatternEntry {
+        id: 1,
+        name: "slow-rain",
+        constructor: || Pattern::SlowRain(SlowRain::default()),
+    },
+    PatternEntry {
+        id: 2,
+        name: "christmas-puke",
+        constructor: || Pattern::ChristmasPuke(ChristmasPuke::default()),
+    },
+    PatternEntry {
+        id: 3,
+        name: "slices",
+        constructor: || Pattern::Slices(Slices::default()),
+    },
+    PatternEntry {
+        id: 4,
+        name: "fireflies",
+        constructor: || Pattern::Fireflies(Fireflies::default()),
+    },
+    PatternEntry {
+        id: 5,
+        name: "life",
+        constructor: || Pattern::Life(Life::default()),
+    },
+    PatternEntry {
+        id: 6,
+        name: "plasma",
+        constructor: || Pattern::Plasma(Plasma::default()),
+    },
+    PatternEntry {
+        id: 7,
+        name: "fire",
+        constructor: || Pattern::Fire(Fire::default()),
+    },
+    PatternEntry {
+        id: 8,
+        name: "snake",
+        constructor: || Pattern::Snake(Snake::default()),
+    },
+    PatternEntry {
+        id: 9,
+        name: "bouncing-balls",
+        constructor: || Pattern::BouncingBalls(BouncingBalls::default()),
+    },
+    PatternEntry {
+        id: 10,
+        name: "blender",
+        constructor: || Pattern::Blender(Blender::default()),
+    },
+    PatternEntry {
+        id: 11,
+        name: "animated-slices",
+        constructor: || Pattern::AnimatedSlices(AnimatedSlices::default()),
+    },
+    PatternEntry {
+        id: 12,
+        name: "rain",
+        constructor: || Pattern::Rain(Rain::default()),
+    },
+    PatternEntry {
+        id: 13,
+        name: "wave",
+        constructor: || Pattern::Wave(Wave::default()),
+    },
+    PatternEntry {
+        id: 14,
+        name: "ripples",
+        constructor: || Pattern::Ripples(Ripples::default()),
+    },
+    PatternEntry {
+        id: 15,
+        name: "fireworks",
+        constructor: || Pattern::Fireworks(Fireworks::default()),
+    },
+    PatternEntry {
+        id: 16,
+        name: "sprite",
+        constructor: || Pattern::Sprite(Sprite::default()),
+    },
+    PatternEntry {
+        id: 17,
+        name: "scripted",
+        constructor: || Pattern::Scripted(Scripted::default()),
+    },
+];
+
+pub fn iter() -> impl Iterator<Item = &'static PatternEntry> {
+    PATTERNS.iter()
+}
+
+pub fn by_name(name: &str) -> Option<&'static PatternEntry> {
+    PATTERNS.iter().find(|entry| entry.name == name)
+}
+
+pub fn by_id(id: u8) -> Option<&'static PatternEntry> {
+    PATTERNS.iter().find(|entry| entry.id == id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unique_names_and_ids() {
+        for (idx, entry) in PATTERNS.iter().enumerate() {
+            for other in &PATTERNS[idx + 1..] {
+                assert_ne!(entry.id, other.id, "duplicate ID {}", entry.id);
+                assert_ne!(entry.name, other.name, "duplicate name {}", entry.name);
+            }
+        }
+    }
+
+    #[test]
+    fn lookup() {
+        assert!(matches!(
+            by_name("slow-rain").map(PatternEntry::build),
+            Some(Pattern::SlowRain(_))
+        ));
+        assert!(matches!(
+            by_id(3).map(PatternEntry::build),
+            Some(Pattern::Slices(_))
+        ));
+        assert!(by_name("nonexistent").is_none());
+        assert_eq!(by_id(2).map(|entry| entry.name), Some("christmas-puke"));
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{
+    apa106led::{Apa106Led, OFF},
+    particles::spawn_count,
+    patterns::{PatternUpdate, Seeded},
+    vec3::Vec3,
+    voxel::Voxel,
+};
+use rand::prelude::*;
+
+/// Largest number of ripples that can be expanding at once.
+const MAX_RIPPLES: usize = 8;
+
+#[derive(Copy, Clone, Debug)]
+struct Ripple {
+    origin: Vec3,
+
+    /// Time the ripple spawned.
+    start: u32,
+
+    colour: Apa106Led,
+
+    /// Distance to the furthest corner of the cube, where the ripple has faded out completely.
+    max_radius: f32,
+}
+
+/// Spherical shells that spawn at random voxels and expand out to the edges of the cube.
+///
+/// Each shell fades as it grows. Overlapping shells add their colours together.
+#[derive(Clone, Debug)]
+pub struct Ripples {
+    /// Average number of ripples spawned per second.
+    spawn_rate: f32,
+
+    /// Thickness of each shell, voxels.
+    thickness: f32,
+
+    /// Speed the shells grow at, voxels per second.
+    speed: f32,
+
+    ripples: [Option<Ripple>; MAX_RIPPLES],
+
+    last_update: u32,
+
+    rng: SmallRng,
+}
+
+impl Default for Ripples {
+    fn default() -> Self {
+        Self {
+            spawn_rate: 1.5,
+            thickness: 1.2,
+            speed: 2.5,
+            ripples: [None; MAX_RIPPLES],
+            last_update: 0,
+            rng: SmallRng::seed_from_u64(Self::SEED),
+        }
+    }
+}
+
+impl Ripples {
+    /// Set the average number of ripples spawned per second. Negative rates are treated as 0, and
+    /// infinite or NaN rates are ignored.
+    pub fn with_spawn_rate(mut self, spawn_rate: f32) -> Self {
+        if spawn_rate.is_finite() {
+            self.spawn_rate = spawn_rate.max(0.0);
+        }
+
+        self
+    }
+
+    /// Set the thickness of each shell in voxels.
+    pub fn with_thickness(mut self, thickness: f32) -> Self {
+        self.thickness = thickness.max(0.1);
+
+        self
+    }
+
+    /// Set how quickly the shells grow in voxels per second.
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed.max(0.1);
+
+        self
+    }
+
+    fn radius(&self, ripple: &Ripple, time: u32) -> f32 {
+        time.saturating_sub(ripple.start) as f32 / 1000.0 * self.speed
+    }
+
+    /// Start a ripple at a random voxel in a free slot, if there is one.
+    fn spawn(&mut self, time: u32) {
+        if let Some(slot) = self.ripples.iter_mut().find(|ripple| ripple.is_none()) {
+            let voxel = Voxel::from_index_unchecked(self.rng.gen_range(0, 64));
+
+            let furthest = Vec3::new(
+                voxel.x.max(3 - voxel.x) as f32,
+                voxel.y.max(3 - voxel.y) as f32,
+                voxel.z.max(3 - voxel.z) as f32,
+            );
+
+            *slot = Some(Ripple {
+                origin: Vec3::from(voxel),
+                start: time,
+                colour: Apa106Led::from_hsv(self.rng.gen(), 1.0, 1.0),
+                max_radius: furthest.length(),
+            });
+        }
+    }
+}
+
+impl Seeded for Ripples {
+    const SEED: u64 = 0x1ee7_1ee7_1ee7_1ee7;
+
+    fn set_seed(&mut self, seed: u64) {
+        self.rng = SmallRng::seed_from_u64(seed);
+    }
+}
+
+impl PatternUpdate for Ripples {
+    fn update(&mut self, time: u32) {
+        let delta = time.saturating_sub(self.last_update);
+        self.last_update = time;
+
+        for idx in 0..MAX_RIPPLES {
+            if let Some(ripple) = self.ripples[idx] {
+                if self.radius(&ripple, time) >= ripple.max_radius {
+                    self.ripples[idx] = None;
+                }
+            }
+        }
+
+        for _ in 0..spawn_count(self.spawn_rate, delta, MAX_RIPPLES, &mut self.rng) {
+            self.spawn(time);
+        }
+    }
+
+    fn pixel_at(&self, idx: usize, time: u32) -> Apa106Led {
+        let voxel = Vec3::from(Voxel::from_index_unchecked(idx));
+
+        self.ripples.iter().flatten().fold(OFF, |colour, ripple| {
+            let radius = self.radius(ripple, time);
+
+            let shell = 1.0 - (voxel.distance(ripple.origin) - radius).abs() * 2.0 / self.thickness;
+            let fade = 1.0 - radius / ripple.max_radius;
+
+            if shell > 0.0 && fade > 0.0 {
+                colour + ripple.colour.fade(shell * fade)
+            } else {
+                colour
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_and_fades() {
+        let mut ripples = Ripples::default().with_spawn_rate(0.0);
+
+        ripples.spawn(0);
+
+        let ripple = ripples.ripples[0].unwrap();
+        let centre = ripple.origin.to_voxel_clamped().into_index_unchecked();
+
+        assert_ne!(ripples.pixel_at(centre, 0), OFF);
+
+        // Shell has moved out past the centre
+        let later = (2000.0 / ripples.speed) as u32;
+
+        assert_eq!(ripples.pixel_at(centre, later), OFF);
+
+        let end = (ripple.max_radius / ripples.speed * 1000.0) as u32 + 1;
+
+        ripples.update(end);
+
+        assert!(ripples.ripples.iter().all(Option::is_none));
+        assert!((0..64).all(|idx| ripples.pixel_at(idx, end) == OFF));
+    }
+
+    #[test]
+    fn overlapping_shells_blend() {
+        let mut ripples = Ripples::default().with_spawn_rate(0.0);
+
+        ripples.spawn(0);
+        ripples.ripples[1] = ripples.ripples[0];
+
+        let single = Ripples {
+            ripples: [ripples.ripples[0], None, None, None, None, None, None, None],
+            ..ripples.clone()
+        };
+
+        let idx = ripples.ripples[0]
+            .unwrap()
+            .origin
+            .to_voxel_clamped()
+            .into_index_unchecked();
+
+        assert_eq!(
+            ripples.pixel_at(idx, 0),
+            single.pixel_at(idx, 0) + single.pixel_at(idx, 0)
+        );
+    }
+}
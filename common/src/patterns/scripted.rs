@@ -0,0 +1,164 @@
+use crate::{
+    apa106led::{Apa106Led, OFF},
+    patterns::PatternUpdate,
+    vm::{self, Inputs, VmError},
+    voxel::Voxel,
+};
+
+/// Longest program a [`Scripted`] pattern can hold, in bytes.
+pub const MAX_PROGRAM_LEN: usize = 256;
+
+/// Program run by default: a rainbow drifting up through the cube.
+pub const DEFAULT_PROGRAM: &str = "
+    t 4000 div
+    z 8 div add
+    x y add 24 div add
+    1 1 hsv
+";
+
+/// Runs a [`vm`] program for every voxel, so new patterns can be loaded as data without
+/// reflashing the firmware.
+///
+/// Any voxel whose program fails is drawn black.
+#[derive(Clone, Debug)]
+pub struct Scripted {
+    program: [u8; MAX_PROGRAM_LEN],
+
+    len: usize,
+
+    /// Largest number of instructions run per voxel.
+    budget: u32,
+
+    /// Period of the program, ms, if it repeats.
+    cycle_length: Option<u32>,
+
+    /// Whether `cycle_length` was set with [`Scripted::with_cycle_length`] rather than being the
+    /// period of the default program.
+    cycle_length_set: bool,
+}
+
+impl Default for Scripted {
+    fn default() -> Self {
+        let mut program = [0; MAX_PROGRAM_LEN];
+
+        let len = vm::assemble(DEFAULT_PROGRAM, &mut program)
+            .expect("Default program failed to assemble");
+
+        Self {
+            program,
+            len,
+            budget: vm::DEFAULT_BUDGET,
+            // The default program's hue wraps every 4 seconds
+            cycle_length: Some(4000),
+            cycle_length_set: false,
+        }
+    }
+}
+
+impl Scripted {
+    /// Load a bytecode program.
+    ///
+    /// The program is run once for the first voxel to check it produces a colour, so most
+    /// mistakes are caught on load instead of rendering a black cube.
+    pub fn with_program(mut self, code: &[u8]) -> Result<Self, VmError> {
+        let program = self
+            .program
+            .get_mut(0..code.len())
+            .ok_or(VmError::ProgramTooLong(code.len()))?;
+
+        program.copy_from_slice(code);
+        self.len = code.len();
+
+        if !self.cycle_length_set {
+            self.cycle_length = None;
+        }
+
+        self.run(0, 0)?;
+
+        Ok(self)
+    }
+
+    /// Set the largest number of instructions run per voxel, from 1 to [`vm::MAX_BUDGET`].
+    pub fn with_budget(mut self, budget: u32) -> Self {
+        self.budget = budget.clamp(1, vm::MAX_BUDGET);
+
+        self
+    }
+
+    /// Set the period of the program in ms, so the pattern can be ended cleanly at the end of a
+    /// cycle. Without this, loading a program clears the period of the default program, as the
+    /// period of arbitrary programs isn't known.
+    pub fn with_cycle_length(mut self, cycle_length: u32) -> Self {
+        self.cycle_length = Some(cycle_length.max(1));
+        self.cycle_length_set = true;
+
+        self
+    }
+
+    fn run(&self, idx: usize, time: u32) -> Result<Apa106Led, VmError> {
+        let voxel = Voxel::from_index_unchecked(idx);
+
+        let inputs = Inputs {
+            x: voxel.x as f32,
+            y: voxel.y as f32,
+            z: voxel.z as f32,
+            t: time as f32,
+        };
+
+        vm::run(&self.program[0..self.len], inputs, self.budget)
+    }
+}
+
+impl PatternUpdate for Scripted {
+    fn pixel_at(&self, idx: usize, time: u32) -> Apa106Led {
+        self.run(idx, time).unwrap_or(OFF)
+    }
+
+    fn cycle_length(&self) -> Option<u32> {
+        self.cycle_length
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_program_runs() {
+        let scripted = Scripted::default();
+
+        assert!((0..64).all(|idx| scripted.run(idx, 1234).is_ok()));
+    }
+
+    #[test]
+    fn load_program() {
+        let mut code = [0; 64];
+        let len = vm::assemble("x 3 div 0 0 rgb", &mut code).unwrap();
+
+        let scripted = Scripted::default().with_program(&code[0..len]).unwrap();
+
+        let corner = Voxel { x: 3, y: 0, z: 0 }.into_index_unchecked();
+
+        assert_eq!(scripted.pixel_at(corner, 0).red, 255);
+        assert_eq!(scripted.cycle_length(), None);
+
+        // An explicit period is kept whichever order the builder methods are called in
+        let scripted = Scripted::default()
+            .with_cycle_length(1500)
+            .with_program(&code[0..len])
+            .unwrap();
+
+        assert_eq!(scripted.cycle_length(), Some(1500));
+
+        assert_eq!(
+            Scripted::default().with_program(&[vm::Op::Add as u8]).err(),
+            Some(VmError::StackUnderflow { address: 0 })
+        );
+        assert_eq!(
+            Scripted::default()
+                .with_program(&[0; MAX_PROGRAM_LEN + 1])
+                .err(),
+            Some(VmError::ProgramTooLong(MAX_PROGRAM_LEN + 1))
+        );
+    }
+}
@@ -1,49 +1,11 @@
-use crate::{apa106led::Apa106Led, patterns::PatternUpdate, voxel::Voxel};
+use crate::{
+    apa106led::Apa106Led,
+    patterns::PatternUpdate,
+    voxel::{Axis, Voxel},
+};
 
-#[derive(Debug, Copy, Clone)]
-enum Direction {
-    /// Left to right
-    X,
-
-    /// Front to back
-    Y,
-
-    /// Top down
-    Z,
-}
-
-impl Direction {
-    fn next_dir(self) -> Self {
-        match self {
-            Self::X => Self::Y,
-            Self::Y => Self::Z,
-            Self::Z => Self::X,
-        }
-    }
-
-    fn colour(self) -> Apa106Led {
-        match self {
-            // Red
-            Self::X => Apa106Led {
-                red: 255,
-                green: 0,
-                blue: 0,
-            },
-            // Green
-            Self::Y => Apa106Led {
-                red: 0,
-                green: 255,
-                blue: 0,
-            },
-            // White
-            Self::Z => Apa106Led {
-                red: 255,
-                green: 255,
-                blue: 255,
-            },
-        }
-    }
-}
+/// Longest slice fade time, ms. Keeps the cycle length of both slices patterns in range.
+pub(crate) const MAX_FADE_TIME: u32 = 60_000;
 
 #[derive(Debug, Copy, Clone)]
 enum Stage {
@@ -56,31 +18,79 @@ pub struct Slices {
     /// Slice fade in/out time, ms.
     fade_time: u32,
 
-    current_colour: Apa106Led,
+    /// Slice colour for each axis, in X, Y, Z order.
+    colours: [Apa106Led; 3],
 
     /// Slice brightnesses
     brightnesses: [f32; 4],
 
     /// Animation direction
-    dir: Direction,
+    dir: Axis,
 
-    // /// Slice index
-    // slice_idx: usize,
     stage: Stage,
 
     threshold: u32,
 }
 
+impl Slices {
+    /// Set the slice fade in/out time in ms, from 1 ms to one minute.
+    pub fn with_fade_time(mut self, fade_time: u32) -> Self {
+        self.fade_time = fade_time.clamp(1, MAX_FADE_TIME);
+        self.threshold = self.fade_time;
+
+        self
+    }
+
+    /// Set the slice colours for each axis, in X, Y, Z order.
+    pub fn with_colours(mut self, colours: [Apa106Led; 3]) -> Self {
+        self.colours = colours;
+
+        self
+    }
+
+    /// Set the axis the first set of slices moves along.
+    pub fn with_axis(mut self, axis: Axis) -> Self {
+        self.dir = axis;
+
+        self
+    }
+
+    fn colour(&self) -> Apa106Led {
+        match self.dir {
+            Axis::X => self.colours[0],
+            Axis::Y => self.colours[1],
+            Axis::Z => self.colours[2],
+        }
+    }
+}
+
 impl Default for Slices {
     fn default() -> Self {
-        let dir = Direction::Z;
-
         let fade_time = 1000;
 
         Self {
             fade_time,
-            current_colour: dir.colour(),
-            dir,
+            colours: [
+                // Red
+                Apa106Led {
+                    red: 255,
+                    green: 0,
+                    blue: 0,
+                },
+                // Green
+                Apa106Led {
+                    red: 0,
+                    green: 255,
+                    blue: 0,
+                },
+                // White
+                Apa106Led {
+                    red: 255,
+                    green: 255,
+                    blue: 255,
+                },
+            ],
+            dir: Axis::Z,
             brightnesses: [0.0f32; 4],
             stage: Stage::FadeIn { idx: 0 },
             threshold: fade_time,
@@ -89,15 +99,12 @@ impl Default for Slices {
 }
 
 impl PatternUpdate for Slices {
-    type CycleCounter = u32;
-
-    fn pixel_at(&mut self, idx: usize, time: u32) -> Apa106Led {
+    fn update(&mut self, time: u32) {
         let brightness = (time % self.fade_time) as f32 / self.fade_time as f32;
 
-        // TODO: Move to a setup method
         // Past end of current stage. Transition state to next phase.
         if time >= self.threshold {
-            self.threshold = time + self.fade_time;
+            self.threshold = time.saturating_add(self.fade_time);
 
             self.stage = match self.stage {
                 // Move on to next slice
@@ -106,7 +113,7 @@ impl PatternUpdate for Slices {
                 Stage::FadeIn { idx: _ } => Stage::FadeOut,
                 // Finished fading out. Reset to zero slice index, change direction
                 Stage::FadeOut => {
-                    self.dir = self.dir.next_dir();
+                    self.dir = self.dir.next();
                     self.brightnesses.iter_mut().for_each(|b| *b = 0.0);
 
                     Stage::FadeIn { idx: 0 }
@@ -114,37 +121,28 @@ impl PatternUpdate for Slices {
             };
         }
 
-        // TODO: Move to a pre-frame method
-        if idx == 0 {
-            match self.stage {
-                Stage::FadeIn { idx } => {
-                    self.brightnesses[idx] = brightness.max(self.brightnesses[idx]);
-                }
-                Stage::FadeOut => self
-                    .brightnesses
-                    .iter_mut()
-                    .for_each(|b| *b = (1.0 - brightness).min(*b)),
+        match self.stage {
+            Stage::FadeIn { idx } => {
+                self.brightnesses[idx] = brightness.max(self.brightnesses[idx]);
             }
+            Stage::FadeOut => self
+                .brightnesses
+                .iter_mut()
+                .for_each(|b| *b = (1.0 - brightness).min(*b)),
         }
+    }
 
-        let voxel = Voxel::from_index(idx);
+    fn pixel_at(&self, idx: usize, _time: u32) -> Apa106Led {
+        let voxel = Voxel::from_index_unchecked(idx);
 
         // Voxel coordinate along current slice axis
-        let voxel_pos = match self.dir {
-            Direction::X => voxel.x,
-            Direction::Y => voxel.y,
-            Direction::Z => voxel.z,
-        };
-
-        let colour = self.dir.colour();
+        let voxel_pos = self.dir.coord(voxel);
 
-        colour.fade(self.brightnesses[voxel_pos as usize])
+        self.colour().fade(self.brightnesses[voxel_pos as usize])
     }
 
-    fn completed_cycles(&self, time: u32) -> Self::CycleCounter {
+    fn cycle_length(&self) -> Option<u32> {
         // Fade for 4 voxels per direction + fadeout time * 3 directions
-        let total_time = self.fade_time * 5 * 3;
-
-        time / total_time
+        Some(self.fade_time * 5 * 3)
     }
 }
@@ -1,9 +1,11 @@
 use crate::{
     apa106led::{Apa106Led, OFF, WARM_WHITE},
-    patterns::PatternUpdate,
+    patterns::{PatternUpdate, Seeded},
+    vec3::Vec3,
     voxel::Voxel,
 };
 use core::f32::consts::PI;
+#[cfg(not(test))]
 use micromath::F32Ext;
 use rand::prelude::*;
 
@@ -21,84 +23,151 @@ pub struct SlowRain {
     /// How long a drop takes to go from the top to the bottom of the cube.
     drop_duration: u32,
 
+    /// Drop colour.
+    colour: Apa106Led,
+
+    /// Chance out of 16 that a column starts with a drop in it.
+    density: u8,
+
     /// Each column gets an offset so drops don't all fall together.
     offsets: [u8; 16],
 
     /// Turn columns on or off.
     mask: [Mask; 16],
 
+    seed: u64,
+
     rng: SmallRng,
 }
 
 impl Default for SlowRain {
     fn default() -> Self {
-        let mut rng = SmallRng::seed_from_u64(0xdead_beef_cafe_babe);
+        let mut pattern = Self {
+            drop_duration: 2000,
+            colour: WARM_WHITE,
+            density: 8,
+            offsets: [0u8; 16],
+            mask: [Mask::Hidden; 16],
+            seed: Self::SEED,
+            rng: SmallRng::seed_from_u64(Self::SEED),
+        };
+
+        pattern.randomise();
 
-        let mut offsets = [0u8; 16];
-        let mut mask = [Mask::Hidden; 16];
+        pattern
+    }
+}
 
-        rng.fill_bytes(&mut offsets);
+impl SlowRain {
+    /// Set how long a drop takes to fall from the top to the bottom of the cube, in ms.
+    pub fn with_drop_duration(mut self, drop_duration: u32) -> Self {
+        self.drop_duration = drop_duration.max(1);
 
-        mask.iter_mut().for_each(|mask| {
-            let i = rng.next_u32() % 16;
+        self
+    }
 
-            // Constant controls how many drops are present in the cube
-            *mask = if i < 8 { Mask::Visible } else { Mask::Hidden };
-        });
+    pub fn with_colour(mut self, colour: Apa106Led) -> Self {
+        self.colour = colour;
 
-        Self {
-            drop_duration: 2000,
-            rng,
-            offsets,
-            mask,
-        }
+        self
     }
-}
 
-impl PatternUpdate for SlowRain {
-    type CycleCounter = u32;
+    /// Set the chance out of 16 that each column starts with a drop in it.
+    ///
+    /// Higher values give heavier rain. Values above 16 are treated as 16.
+    pub fn with_density(mut self, density: u8) -> Self {
+        self.density = density.min(16);
 
-    fn pixel_at(&mut self, idx: usize, time: u32) -> Apa106Led {
-        let voxel = Voxel::from_index(idx);
-        let column_idx = (voxel.x + voxel.y * 4) as usize;
+        self.randomise();
 
-        let column_offset = self.offsets[column_idx] as f32 / 255.0 - 127.0;
-        let mut mask = self.mask[column_idx];
+        self
+    }
 
-        // Length in voxels away from leading point where brightness should be zero
-        let tail_len = 3.0;
+    /// Reseed the RNG and pick new drop offsets and columns.
+    fn randomise(&mut self) {
+        self.rng = SmallRng::seed_from_u64(self.seed);
 
-        // 4 voxels plus a front/back porch for each tail length. This ensures all voxels are blank
-        // between each iteration.
-        let total_scale = 4.0 + (tail_len * 2.0);
+        self.rng.fill_bytes(&mut self.offsets);
+
+        let density = self.density as u32;
+        let rng = &mut self.rng;
+
+        self.mask.iter_mut().for_each(|mask| {
+            let i = rng.next_u32() % 16;
+
+            *mask = if i < density {
+                Mask::Visible
+            } else {
+                Mask::Hidden
+            };
+        });
+    }
+
+    /// Position of a column's drop through its fall, from 0.0 to 1.0.
+    fn time_pos(&self, column_idx: usize, time: u32) -> f32 {
+        let column_offset = self.offsets[column_idx] as f32 / 255.0 - 127.0;
 
         let time_pos = (time % self.drop_duration) as f32 / self.drop_duration as f32;
 
         // Apply offset
-        let time_pos = (time_pos + column_offset).rem_euclid(1.0);
+        (time_pos + column_offset).rem_euclid(1.0)
+    }
+}
 
-        // Reset
-        if time_pos >= 0.99 && mask == Mask::Visible && voxel.z == 3 {
-            loop {
-                let next_idx = (self.rng.next_u32() as usize + column_idx) % 16;
+impl Seeded for SlowRain {
+    const SEED: u64 = 0xdead_beef_cafe_babe;
 
-                // Add a bit of chaos
-                if self.rng.next_u32() % 10 <= 1 {
-                    continue;
-                }
+    fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
 
-                // Find next unlit column
-                if self.mask[next_idx] == Mask::Hidden {
-                    self.mask[column_idx] = Mask::Hidden;
-                    self.mask[next_idx] = Mask::Stage;
+        self.randomise();
+    }
+}
 
-                    break;
+impl PatternUpdate for SlowRain {
+    fn update(&mut self, time: u32) {
+        for column_idx in 0..16 {
+            let time_pos = self.time_pos(column_idx, time);
+            let mask = self.mask[column_idx];
+
+            // Reset. If every column has a drop there's nowhere to move to, so leave it in place.
+            if time_pos >= 0.99 && mask == Mask::Visible && self.mask.contains(&Mask::Hidden) {
+                loop {
+                    let next_idx = (self.rng.next_u32() as usize + column_idx) % 16;
+
+                    // Add a bit of chaos
+                    if self.rng.next_u32() % 10 <= 1 {
+                        continue;
+                    }
+
+                    // Find next unlit column
+                    if self.mask[next_idx] == Mask::Hidden {
+                        self.mask[column_idx] = Mask::Hidden;
+                        self.mask[next_idx] = Mask::Stage;
+
+                        break;
+                    }
                 }
+            } else if time_pos <= 0.01 && mask == Mask::Stage {
+                self.mask[column_idx] = Mask::Visible;
             }
-        } else if time_pos <= 0.01 && mask == Mask::Stage {
-            self.mask[column_idx] = Mask::Visible;
-            mask = Mask::Visible;
         }
+    }
+
+    fn pixel_at(&self, idx: usize, time: u32) -> Apa106Led {
+        let voxel = Voxel::from_index_unchecked(idx);
+        let column_idx = (voxel.x + voxel.y * 4) as usize;
+
+        let mask = self.mask[column_idx];
+
+        // Length in voxels away from leading point where brightness should be zero
+        let tail_len = 3.0;
+
+        // 4 voxels plus a front/back porch for each tail length. This ensures all voxels are blank
+        // between each iteration.
+        let total_scale = 4.0 + (tail_len * 2.0);
+
+        let time_pos = self.time_pos(column_idx, time);
 
         // Subtract time instead of adding to it to flip pattern the right way up
         let time_pos = 1.0 - time_pos;
@@ -106,25 +175,26 @@ impl PatternUpdate for SlowRain {
         // Off the top of the cube by tail_len to below cube by tail_len
         let scaled_time_pos = -tail_len + (time_pos * total_scale);
 
-        let voxel_pos = voxel.z as f32;
+        // Leading point of the drop falling through this voxel's column
+        let drop_pos = Vec3::new(voxel.x as f32, voxel.y as f32, scaled_time_pos);
 
-        // Can check sign later for different leading/trailing behaviours
-        let distance = voxel_pos - scaled_time_pos;
+        let distance = Vec3::from(voxel).distance(drop_pos);
 
         if mask == Mask::Visible {
             // 1.0 - 0.0 clamped
-            let distance = (distance / tail_len).abs().min(1.0);
+            let distance = (distance / tail_len).min(1.0);
 
             // Smoother transition
             let distance = ((distance * PI).cos() + 1.0) / 2.0;
 
-            WARM_WHITE.fade(distance)
+            self.colour.fade(distance)
         } else {
             OFF
         }
     }
 
-    fn completed_cycles(&self, time: u32) -> Self::CycleCounter {
-        time / self.drop_duration
+    fn cycle_length(&self) -> Option<u32> {
+        // Drops have different offsets, so one cycle is the time for every column to drop once
+        Some(self.drop_duration)
     }
 }
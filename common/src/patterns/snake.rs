@@ -0,0 +1,475 @@
+use crate::{
+    apa106led::{Apa106Led, OFF},
+    clock::FixedStep,
+    input::InputEvent,
+    patterns::{PatternUpdate, Seeded},
+    voxel::{Connectivity, Edges, Voxel},
+};
+use rand::prelude::*;
+
+/// Largest number of simulation steps run in one frame. Jumps further forward than this skip
+/// ahead instead of catching up.
+const MAX_STEPS: u32 = 16;
+
+/// Length of a newly spawned snake.
+const START_LEN: usize = 3;
+
+/// Number of steps the death animation lasts for.
+const DEATH_STEPS: u8 = 8;
+
+/// How far an axis has to be pushed to steer along it.
+const STEER_THRESHOLD: f32 = 0.5;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Phase {
+    /// Moving towards the food.
+    Moving,
+
+    /// Trapped with no safe move. Counts down the remaining steps of the death animation.
+    Dying(u8),
+}
+
+/// A snake that wanders around the cube looking for food.
+///
+/// The snake takes the shortest path to the food that avoids its own body. When no path exists,
+/// it makes any safe move it can to buy time for its tail to move out of the way. When it's
+/// completely trapped, it flashes and a new game starts.
+///
+/// Axes 0 and 1 steer the snake along X and Y, and holding buttons 0 and 1 makes it climb and
+/// dive. It goes back to finding food by itself once let go.
+#[derive(Clone, Debug)]
+pub struct Snake {
+    head_colour: Apa106Led,
+
+    body_colour: Apa106Led,
+
+    food_colour: Apa106Led,
+
+    /// Body segments, head first. Only the first `len` entries are used.
+    body: [Voxel; 64],
+
+    len: usize,
+
+    /// Segment number of the snake at each voxel by frame index, counting from 1 at the head, or
+    /// 0 for empty voxels.
+    cells: [u8; 64],
+
+    food: Voxel,
+
+    phase: Phase,
+
+    /// Number of games that have ended.
+    games: u32,
+
+    /// Direction the player is steering along each axis, from -1 to 1.
+    heading: [i8; 3],
+
+    fixed_step: FixedStep,
+
+    rng: SmallRng,
+}
+
+impl Default for Snake {
+    fn default() -> Self {
+        let mut pattern = Self {
+            head_colour: Apa106Led {
+                red: 255,
+                green: 255,
+                blue: 255,
+            },
+            body_colour: Apa106Led {
+                red: 0,
+                green: 255,
+                blue: 40,
+            },
+            food_colour: Apa106Led {
+                red: 255,
+                green: 0,
+                blue: 0,
+            },
+            body: [Voxel::default(); 64],
+            len: 0,
+            cells: [0; 64],
+            food: Voxel::default(),
+            phase: Phase::Moving,
+            games: 0,
+            heading: [0; 3],
+            fixed_step: FixedStep::new(250, MAX_STEPS),
+            rng: SmallRng::seed_from_u64(Self::SEED),
+        };
+
+        pattern.restart();
+
+        pattern
+    }
+}
+
+impl Snake {
+    /// Set the time between moves in ms.
+    pub fn with_step_time(mut self, step_time: u32) -> Self {
+        self.fixed_step.set_step_time(step_time);
+
+        self
+    }
+
+    /// Set the colours of the snake's head, its body and the food.
+    pub fn with_colours(mut self, head: Apa106Led, body: Apa106Led, food: Apa106Led) -> Self {
+        self.head_colour = head;
+        self.body_colour = body;
+        self.food_colour = food;
+
+        self
+    }
+
+    /// Start a new game with a short snake in a random position.
+    fn restart(&mut self) {
+        let head = Voxel::from_index_unchecked(self.rng.gen_range(0, 64));
+
+        // All segments start on top of each other and unfurl as the snake moves
+        self.body = [head; 64];
+        self.len = START_LEN;
+        self.phase = Phase::Moving;
+
+        self.update_cells();
+        self.place_food();
+    }
+
+    fn update_cells(&mut self) {
+        self.cells = [0; 64];
+
+        // Work from the tail forwards so segments stacked on the same voxel show the one nearest
+        // the head
+        for (segment, voxel) in self.body[0..self.len].iter().enumerate().rev() {
+            self.cells[voxel.into_index_unchecked()] = segment as u8 + 1;
+        }
+    }
+
+    /// Put food on a random empty voxel. Does nothing if the snake fills the cube.
+    fn place_food(&mut self) {
+        let empty = self.cells.iter().filter(|cell| **cell == 0).count();
+
+        if empty == 0 {
+            return;
+        }
+
+        let nth = self.rng.gen_range(0, empty);
+
+        if let Some((idx, _)) = self
+            .cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| **cell == 0)
+            .nth(nth)
+        {
+            self.food = Voxel::from_index_unchecked(idx);
+        }
+    }
+
+    /// Whether the head can move into a voxel on the next step without hitting the body.
+    ///
+    /// The tail moves out of the way as the head moves, so its voxel counts as free unless
+    /// another segment is stacked on it.
+    fn is_free(&self, voxel: Voxel) -> bool {
+        match self.cells[voxel.into_index_unchecked()] {
+            0 => true,
+            segment => segment as usize == self.len,
+        }
+    }
+
+    /// First move along the shortest path from the head to the food, found with a breadth first
+    /// search.
+    fn path_to_food(&self) -> Option<Voxel> {
+        let head = self.body[0];
+
+        // Frame index of the voxel each visited voxel was reached from
+        let mut came_from = [None; 64];
+        let mut queue = [Voxel::default(); 64];
+        let (mut read, mut write) = (0, 0);
+
+        came_from[head.into_index_unchecked()] = Some(head);
+        queue[write] = head;
+        write += 1;
+
+        while read < write {
+            let current = queue[read];
+            read += 1;
+
+            if current == self.food {
+                // Walk back along the path to find the voxel next to the head
+                let mut step = current;
+
+                while let Some(prev) = came_from[step.into_index_unchecked()] {
+                    if prev == head {
+                        return Some(step);
+                    }
+
+                    step = prev;
+                }
+
+                return None;
+            }
+
+            for next in current.neighbours(Connectivity::Six, Edges::Bounded) {
+                let idx = next.into_index_unchecked();
+
+                if came_from[idx].is_none() && self.is_free(next) {
+                    came_from[idx] = Some(current);
+                    queue[write] = next;
+                    write += 1;
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Any move that doesn't hit the body, preferring the one with the most free space around it.
+    fn safe_move(&self) -> Option<Voxel> {
+        self.body[0]
+            .neighbours(Connectivity::Six, Edges::Bounded)
+            .filter(|next| self.is_free(*next))
+            .max_by_key(|next| {
+                next.neighbours(Connectivity::Six, Edges::Bounded)
+                    .filter(|n| self.is_free(*n))
+                    .count()
+            })
+    }
+
+    /// Move in a direction the player is steering, if it doesn't hit the body or leave the cube.
+    fn steered_move(&self) -> Option<Voxel> {
+        let head = self.body[0];
+        let [dx, dy, dz] = self.heading;
+
+        [(dx, 0, 0), (0, dy, 0), (0, 0, dz)]
+            .iter()
+            .filter(|offset| **offset != (0, 0, 0))
+            .filter_map(|(dx, dy, dz)| {
+                Voxel::new(
+                    (head.x as i8 + dx) as u8,
+                    (head.y as i8 + dy) as u8,
+                    (head.z as i8 + dz) as u8,
+                )
+                .ok()
+            })
+            .find(|next| self.is_free(*next))
+    }
+
+    /// Advance the game by one move.
+    fn step(&mut self) {
+        if let Phase::Dying(remaining) = self.phase {
+            if remaining > 1 {
+                self.phase = Phase::Dying(remaining - 1);
+            } else {
+                self.games += 1;
+
+                self.restart();
+            }
+
+            return;
+        }
+
+        let next = match self
+            .steered_move()
+            .or_else(|| self.path_to_food())
+            .or_else(|| self.safe_move())
+        {
+            Some(next) => next,
+            None => {
+                self.phase = Phase::Dying(DEATH_STEPS);
+
+                return;
+            }
+        };
+
+        let ate = next == self.food;
+
+        if ate && self.len < self.body.len() {
+            self.len += 1;
+        }
+
+        self.body.copy_within(0..self.len - 1, 1);
+        self.body[0] = next;
+
+        self.update_cells();
+
+        if ate {
+            self.place_food();
+        }
+
+        // Filled the whole cube. Nowhere left to go, so end the game.
+        if self.len == self.body.len() {
+            self.phase = Phase::Dying(DEATH_STEPS);
+        }
+    }
+}
+
+impl Seeded for Snake {
+    const SEED: u64 = 0x5a4e_5a4e_5a4e_5a4e;
+
+    fn set_seed(&mut self, seed: u64) {
+        self.rng = SmallRng::seed_from_u64(seed);
+
+        self.restart();
+    }
+}
+
+impl PatternUpdate for Snake {
+    fn update(&mut self, time: u32) {
+        for _ in self.fixed_step.steps(time) {
+            self.step();
+        }
+    }
+
+    fn pixel_at(&self, idx: usize, _time: u32) -> Apa106Led {
+        let segment = self.cells[idx];
+
+        match self.phase {
+            // Flash the body on and off, getting dimmer each time
+            Phase::Dying(remaining) if segment > 0 => {
+                if remaining % 2 == 0 {
+                    self.food_colour.fade(remaining as f32 / DEATH_STEPS as f32)
+                } else {
+                    OFF
+                }
+            }
+            Phase::Dying(_) => OFF,
+            Phase::Moving => match segment {
+                0 if Voxel::from_index_unchecked(idx) == self.food => self.food_colour,
+                0 => OFF,
+                1 => self.head_colour,
+                // Fade the body towards the tail
+                segment => self
+                    .body_colour
+                    .fade(1.0 - 0.7 * (segment - 1) as f32 / self.len as f32),
+            },
+        }
+    }
+
+    fn completed_cycles(&self, _time: u32) -> u32 {
+        // One cycle is one game
+        self.games
+    }
+
+    fn input(&mut self, event: InputEvent) {
+        let steer = |value: f32| {
+            if value > STEER_THRESHOLD {
+                1
+            } else if value < -STEER_THRESHOLD {
+                -1
+            } else {
+                0
+            }
+        };
+
+        match event {
+            InputEvent::Axis { axis: 0, value } => self.heading[0] = steer(value),
+            InputEvent::Axis { axis: 1, value } => self.heading[1] = steer(value),
+            InputEvent::ButtonDown(0) => self.heading[2] = 1,
+            InputEvent::ButtonDown(1) => self.heading[2] = -1,
+            InputEvent::ButtonUp(0) | InputEvent::ButtonUp(1) => self.heading[2] = 0,
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eats_and_grows() {
+        let mut snake = Snake::default();
+
+        for _ in 0..200 {
+            snake.step();
+
+            if snake.len > START_LEN {
+                break;
+            }
+        }
+
+        assert!(snake.len > START_LEN);
+        assert_eq!(snake.cells[snake.body[0].into_index_unchecked()], 1);
+        assert_ne!(snake.cells[snake.food.into_index_unchecked()], 1);
+    }
+
+    #[test]
+    fn never_hits_itself() {
+        let mut snake = Snake::default().with_seed(42);
+
+        for _ in 0..2000 {
+            snake.step();
+
+            if snake.phase == Phase::Moving {
+                let head = snake.body[0];
+
+                // Segments are stacked on the head at the start of each game
+                assert!(snake.body[1..snake.len]
+                    .iter()
+                    .skip_while(|segment| **segment == head)
+                    .all(|segment| *segment != head));
+            }
+        }
+    }
+
+    #[test]
+    fn restarts_when_trapped() {
+        let mut snake = Snake::default();
+
+        // Box the head into a corner with nowhere to go
+        snake.body[0] = Voxel { x: 0, y: 0, z: 0 };
+        snake.body[1] = Voxel { x: 1, y: 0, z: 0 };
+        snake.body[2] = Voxel { x: 0, y: 1, z: 0 };
+        snake.body[3] = Voxel { x: 0, y: 0, z: 1 };
+        snake.body[4] = Voxel { x: 0, y: 0, z: 1 };
+        snake.len = 5;
+        snake.update_cells();
+        snake.food = Voxel { x: 3, y: 3, z: 3 };
+
+        snake.step();
+
+        assert_eq!(snake.phase, Phase::Dying(DEATH_STEPS));
+
+        for _ in 0..DEATH_STEPS {
+            snake.step();
+        }
+
+        assert_eq!(snake.phase, Phase::Moving);
+        assert_eq!(snake.len, START_LEN);
+        assert_eq!(snake.completed_cycles(0), 1);
+    }
+
+    #[test]
+    fn steers_with_input() {
+        let mut snake = Snake::default();
+
+        snake.body[0] = Voxel { x: 1, y: 1, z: 1 };
+        snake.body[1] = Voxel { x: 1, y: 2, z: 1 };
+        snake.body[2] = Voxel { x: 1, y: 3, z: 1 };
+        snake.update_cells();
+        snake.food = Voxel { x: 0, y: 0, z: 0 };
+
+        snake.input(InputEvent::Axis {
+            axis: 0,
+            value: 1.0,
+        });
+        snake.step();
+
+        assert_eq!(snake.body[0], Voxel { x: 2, y: 1, z: 1 });
+
+        snake.input(InputEvent::ButtonDown(0));
+        snake.input(InputEvent::Axis {
+            axis: 0,
+            value: 0.0,
+        });
+        snake.step();
+
+        assert_eq!(snake.body[0], Voxel { x: 2, y: 1, z: 2 });
+
+        // Let go, so the snake heads back towards the food
+        snake.input(InputEvent::ButtonUp(0));
+        snake.step();
+
+        assert_eq!(snake.body[0].manhattan_distance(snake.food), 4);
+    }
+}
@@ -0,0 +1,211 @@
+use crate::{
+    animation::{Animation, Keyframe},
+    apa106led::Apa106Led,
+    palette::Palette,
+    patterns::PatternUpdate,
+};
+
+/// A cube that grows out from the centre, flashes and collapses again.
+pub const PULSE: Animation = Animation::new(
+    Palette::new(&[
+        Apa106Led::OFF,
+        Apa106Led {
+            red: 255,
+            green: 0,
+            blue: 60,
+        },
+        Apa106Led {
+            red: 120,
+            green: 0,
+            blue: 255,
+        },
+        Apa106Led {
+            red: 255,
+            green: 255,
+            blue: 255,
+        },
+    ]),
+    &[
+        Keyframe::from_hex(
+            "0000 0000 0000 0000
+             0000 0110 0110 0000
+             0000 0110 0110 0000
+             0000 0000 0000 0000",
+            400,
+        ),
+        Keyframe::from_hex(
+            "2222 2222 2222 2222
+             2222 2112 2112 2222
+             2222 2112 2112 2222
+             2222 2222 2222 2222",
+            300,
+        ),
+        Keyframe::from_hex(
+            "3333 3333 3333 3333
+             3333 3223 3223 3333
+             3333 3223 3223 3333
+             3333 3333 3333 3333",
+            150,
+        ),
+        Keyframe::from_hex(
+            "0000 0000 0000 0000
+             0000 0000 0000 0000
+             0000 0000 0000 0000
+             0000 0000 0000 0000",
+            600,
+        ),
+    ],
+);
+
+/// How a [`Sprite`] repeats once it reaches the end of its animation.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Playback {
+    /// Start again from the first keyframe.
+    Loop,
+
+    /// Play backwards to the first keyframe, then forwards again.
+    PingPong,
+}
+
+/// Plays back a keyframe [`Animation`].
+#[derive(Clone, Debug)]
+pub struct Sprite {
+    animation: Animation,
+
+    playback: Playback,
+
+    /// Blend smoothly from each keyframe into the next instead of cutting between them.
+    interpolate: bool,
+}
+
+impl Default for Sprite {
+    fn default() -> Self {
+        Self {
+            animation: PULSE,
+            playback: Playback::Loop,
+            interpolate: true,
+        }
+    }
+}
+
+impl Sprite {
+    pub fn with_animation(mut self, animation: Animation) -> Self {
+        self.animation = animation;
+
+        self
+    }
+
+    pub fn with_playback(mut self, playback: Playback) -> Self {
+        self.playback = playback;
+
+        self
+    }
+
+    /// Set whether keyframes blend smoothly into the next one or cut straight to it.
+    pub fn with_interpolation(mut self, interpolate: bool) -> Self {
+        self.interpolate = interpolate;
+
+        self
+    }
+
+    /// Time for one full cycle of the playback mode, ms.
+    fn cycle_time(&self) -> u32 {
+        let duration = self.animation.duration().max(1);
+
+        match self.playback {
+            Playback::Loop => duration,
+            Playback::PingPong => duration * 2,
+        }
+    }
+}
+
+impl PatternUpdate for Sprite {
+    fn pixel_at(&self, idx: usize, time: u32) -> Apa106Led {
+        let duration = self.animation.duration().max(1);
+        let keyframes = self.animation.keyframes;
+        let last = keyframes.len() - 1;
+
+        let time = time % self.cycle_time();
+
+        let (current, progress) = match self.playback {
+            Playback::Loop => self.animation.keyframe_at(time),
+            // Play the second half of the cycle backwards
+            Playback::PingPong if time >= duration => {
+                self.animation.keyframe_at(duration * 2 - 1 - time)
+            }
+            Playback::PingPong => self.animation.keyframe_at(time),
+        };
+
+        let palette = self.animation.palette;
+        let colour = palette.get(keyframes[current].index_at(idx) as usize);
+
+        if !self.interpolate {
+            return colour;
+        }
+
+        // Ping pong playback holds the last keyframe at the turnaround instead of wrapping
+        let next = match self.playback {
+            Playback::Loop if current == last => 0,
+            Playback::PingPong if current == last => last,
+            _ => current + 1,
+        };
+
+        colour.lerp(
+            palette.get(keyframes[next].index_at(idx) as usize),
+            progress,
+        )
+    }
+
+    fn cycle_length(&self) -> Option<u32> {
+        Some(self.cycle_time())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxel::Voxel;
+
+    #[test]
+    fn playback() {
+        let sprite = Sprite::default().with_interpolation(false);
+        let corner = Voxel { x: 0, y: 0, z: 0 }.into_index_unchecked();
+        let duration = PULSE.duration();
+
+        assert_eq!(sprite.pixel_at(corner, 0), Apa106Led::OFF);
+        assert_eq!(sprite.pixel_at(corner, 500), PULSE.palette.get(2));
+        assert_eq!(
+            sprite.pixel_at(corner, duration + 500),
+            PULSE.palette.get(2)
+        );
+
+        let sprite = sprite.with_playback(Playback::PingPong);
+
+        // Mirrored around the end of the animation
+        assert_eq!(
+            sprite.pixel_at(corner, duration + 100),
+            PULSE.palette.get(0)
+        );
+        assert_eq!(
+            sprite.pixel_at(corner, 2 * duration - 500),
+            PULSE.palette.get(2)
+        );
+        assert_eq!(sprite.completed_cycles(2 * duration), 1);
+    }
+
+    #[test]
+    fn interpolation() {
+        let sprite = Sprite::default();
+        let centre = Voxel { x: 1, y: 1, z: 1 }.into_index_unchecked();
+
+        // Halfway through the first keyframe, blending from colour 1 to colour 1
+        assert_eq!(sprite.pixel_at(centre, 200), PULSE.palette.get(1));
+
+        let corner = Voxel { x: 0, y: 0, z: 0 }.into_index_unchecked();
+
+        assert_eq!(
+            sprite.pixel_at(corner, 200),
+            PULSE.palette.get(0).lerp(PULSE.palette.get(2), 0.5)
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{apa106led::Apa106Led, patterns::PatternUpdate, voxel::Voxel};
+use core::f32::consts::PI;
+#[cfg(not(test))]
+use micromath::F32Ext;
+
+/// Shape of the surface drawn by [`Wave`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WaveFunction {
+    /// Parallel wavefronts moving diagonally across the cube.
+    Travelling,
+
+    /// Circular waves spreading out from the centre of the cube.
+    Ripple,
+
+    /// A surface rocking up and down on the spot with fixed nodes.
+    Standing,
+}
+
+/// A wavy surface through the cube, coloured by height.
+///
+/// The surface is a height field `z = f(x, y, t)`. Each voxel is lit by how close it is to the
+/// surface, so the surface moves smoothly between layers instead of jumping.
+#[derive(Clone, Debug)]
+pub struct Wave {
+    function: WaveFunction,
+
+    /// Wave periods per second.
+    speed: f32,
+
+    /// Hue at the bottom of the cube, 0.0 - 1.0.
+    low_hue: f32,
+
+    /// Hue at the top of the cube, 0.0 - 1.0.
+    high_hue: f32,
+}
+
+impl Default for Wave {
+    fn default() -> Self {
+        Self {
+            function: WaveFunction::Travelling,
+            speed: 0.5,
+            low_hue: 0.66,
+            high_hue: 0.0,
+        }
+    }
+}
+
+impl Wave {
+    pub fn with_function(mut self, function: WaveFunction) -> Self {
+        self.function = function;
+
+        self
+    }
+
+    /// Set how quickly the surface moves in wave periods per second.
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+
+        self
+    }
+
+    /// Set the hues of the lowest and highest points of the surface, from 0.0 to 1.0.
+    pub fn with_hues(mut self, low: f32, high: f32) -> Self {
+        self.low_hue = low;
+        self.high_hue = high;
+
+        self
+    }
+
+    /// Height of the surface above column `x`, `y` in voxels, from `0.0` to `3.0`.
+    fn height(&self, x: f32, y: f32, time: u32) -> f32 {
+        let phase = time as f32 / 1000.0 * self.speed * 2.0 * PI;
+
+        let value = match self.function {
+            WaveFunction::Travelling => ((x + y) * 0.8 - phase).sin(),
+            WaveFunction::Ripple => {
+                let (dx, dy) = (x - 1.5, y - 1.5);
+
+                ((dx * dx + dy * dy).sqrt() * 2.0 - phase).sin()
+            }
+            WaveFunction::Standing => (x * PI / 3.0).cos() * (y * PI / 3.0).cos() * phase.cos(),
+        };
+
+        1.5 + value * 1.5
+    }
+}
+
+impl PatternUpdate for Wave {
+    fn pixel_at(&self, idx: usize, time: u32) -> Apa106Led {
+        let voxel = Voxel::from_index_unchecked(idx);
+
+        let height = self.height(voxel.x as f32, voxel.y as f32, time);
+
+        let level = 1.0 - (voxel.z as f32 - height).abs();
+
+        if level <= 0.0 {
+            return Apa106Led::OFF;
+        }
+
+        let hue = self.low_hue + (self.high_hue - self.low_hue) * height / 3.0;
+
+        Apa106Led::from_hsv(hue, 1.0, level)
+    }
+
+    fn cycle_length(&self) -> Option<u32> {
+        // One cycle is one wave period
+        Some((1000.0 / self.speed.abs()) as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn surface_in_every_column() {
+        for function in [
+            WaveFunction::Travelling,
+            WaveFunction::Ripple,
+            WaveFunction::Standing,
+        ]
+        .iter()
+        {
+            let wave = Wave::default().with_function(*function);
+
+            for time in (0..4000).step_by(250) {
+                for x in 0..4 {
+                    for y in 0..4 {
+                        let lit = (0..4)
+                            .map(|z| Voxel { x, y, z }.into_index_unchecked())
+                            .filter(|idx| wave.pixel_at(*idx, time) != Apa106Led::OFF)
+                            .count();
+
+                        assert!(
+                            (1..=2).contains(&lit),
+                            "{:?} column {}, {} at {}",
+                            function,
+                            x,
+                            y,
+                            time
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
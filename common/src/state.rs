@@ -1,4 +1,89 @@
-use crate::{cube::Cube, patterns::*, transitions::*};
+use crate::{clock::Clock, cube::Cube, input::InputEvent, patterns::*, transitions::*};
+use core::mem;
+use rand::{rngs::SmallRng, RngCore, SeedableRng};
+
+/// Default seed for the RNG that seeds each new pattern.
+const SEED: u64 = 0x5eed_5eed_5eed_5eed;
+
+/// A pattern in the playlist.
+struct PlaylistEntry {
+    pattern: fn() -> Pattern,
+
+    /// Number of cycles to play before moving on to the next entry.
+    cycles: u32,
+
+    /// Transition into the next entry.
+    transition: fn() -> Transition,
+}
+
+/// Patterns played in order, looping back to the start after the last one.
+const PLAYLIST: &[PlaylistEntry] = &[
+    PlaylistEntry {
+        pattern: || Pattern::Rainbow(Rainbow::default()),
+        cycles: 7,
+        transition: || Transition::CrossFade(CrossFade::default()),
+    },
+    PlaylistEntry {
+        pattern: || Pattern::SlowRain(SlowRain::default()),
+        cycles: 10,
+        transition: || Transition::FadeToBlack(FadeToBlack::default()),
+    },
+    PlaylistEntry {
+        pattern: || Pattern::Slices(Slices::default()),
+        cycles: 2,
+        transition: || Transition::CrossFade(CrossFade::default()),
+    },
+    PlaylistEntry {
+        pattern: || Pattern::AnimatedSlices(AnimatedSlices::default()),
+        cycles: 1,
+        transition: || Transition::CrossFade(CrossFade::default()),
+    },
+    PlaylistEntry {
+        pattern: || Pattern::ChristmasPuke(ChristmasPuke::default()),
+        cycles: 7,
+        transition: || Transition::CrossFade(CrossFade::default()),
+    },
+    PlaylistEntry {
+        pattern: || Pattern::Blender(Blender::default()),
+        cycles: 20,
+        transition: || Transition::FadeToBlack(FadeToBlack::default()),
+    },
+    PlaylistEntry {
+        pattern: || Pattern::Rain(Rain::default()),
+        cycles: 20,
+        transition: || Transition::CrossFade(CrossFade::default()),
+    },
+    PlaylistEntry {
+        pattern: || Pattern::Fireflies(Fireflies::default()),
+        cycles: 6,
+        transition: || Transition::FadeFromBlack(FadeFromBlack::default()),
+    },
+];
+
+/// Find where a pattern is in the playlist, if it's in it at all.
+fn playlist_position(pattern: &Pattern) -> Option<usize> {
+    PLAYLIST
+        .iter()
+        .position(|entry| mem::discriminant(&(entry.pattern)()) == mem::discriminant(pattern))
+}
+
+/// Whether a pattern has played for at least `cycles` cycles and can be replaced.
+///
+/// Patterns with a natural end are asked to wind down once they reach `cycles`, then played until
+/// they finish. Other patterns are replaced straight away, at the end of their last cycle.
+fn is_complete(pattern: &mut Pattern, cycles: u32, time: u32) -> bool {
+    if pattern.completed_cycles(time) < cycles {
+        return false;
+    }
+
+    if pattern.has_natural_end() {
+        pattern.wind_down(time);
+
+        pattern.is_finished(time)
+    } else {
+        true
+    }
+}
 
 pub struct TransitionState {
     driver: Transition,
@@ -10,18 +95,100 @@ pub struct State {
     current_start: u32,
     pattern: Pattern,
     transition: Option<TransitionState>,
+
+    /// Picks a new seed for every pattern the playlist starts.
+    rng: SmallRng,
+
+    /// Converts the wall time passed to [`State::drive`] into pattern time.
+    clock: Clock,
+
+    /// Index of the current pattern in [`PLAYLIST`], or `None` if it isn't part of the playlist.
+    playlist_position: Option<usize>,
 }
 
 impl State {
-    pub fn new(pattern: Pattern) -> Self {
+    pub fn new(mut pattern: Pattern) -> Self {
+        pattern.enter();
+
         Self {
+            playlist_position: playlist_position(&pattern),
             pattern,
             transition: None,
             current_start: 0,
+            rng: SmallRng::seed_from_u64(SEED),
+            clock: Clock::default(),
+        }
+    }
+
+    /// Seed the RNG used to seed every pattern the playlist starts, including the current one.
+    ///
+    /// The same seed always plays out the same way. Pass a different seed every boot, for example
+    /// from a hardware entropy source, to stop the cube looking identical each time it's switched
+    /// on.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = SmallRng::seed_from_u64(seed);
+        self.pattern = self.pattern.with_seed(self.rng.next_u64());
+
+        self
+    }
+
+    /// Playback clock, giving the current pattern time, time scale and whether playback is paused.
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// Control playback through the clock directly. Use [`State::earliest_time`] as the floor
+    /// when stepping it.
+    pub fn clock_mut(&mut self) -> &mut Clock {
+        &mut self.clock
+    }
+
+    /// Set how fast patterns play compared to wall time. `1.0` is normal speed, and negative
+    /// values play backwards until the start of the current pattern. See
+    /// [`Clock::set_time_scale`] for the allowed range.
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.clock.set_time_scale(time_scale);
+    }
+
+    /// Freeze the current frame. [`State::drive`] keeps drawing it until playback is resumed.
+    pub fn pause(&mut self) {
+        self.clock.pause();
+    }
+
+    pub fn resume(&mut self) {
+        self.clock.resume();
+    }
+
+    /// Advance playback by a single frame of `frame_time` ms, scaled by the time scale. Used to
+    /// step through a paused pattern.
+    pub fn step(&mut self, frame_time: u32) {
+        let floor = self.earliest_time();
+
+        self.clock.step(frame_time, floor);
+    }
+
+    /// Deliver an input event to the active pattern. During a transition, this is the pattern
+    /// being transitioned to.
+    pub fn input(&mut self, event: InputEvent) {
+        match self.transition.as_mut() {
+            Some(t) => t.next_pattern.input(event),
+            None => self.pattern.input(event),
+        }
+    }
+
+    /// Pattern time can't run backwards past the start of the current pattern or transition.
+    pub fn earliest_time(&self) -> u32 {
+        match &self.transition {
+            Some(t) => t.start.max(self.current_start),
+            None => self.current_start,
         }
     }
 
     fn next_pattern(&mut self, time: u32, new_pattern: Pattern, transition: Option<Transition>) {
+        let mut new_pattern = new_pattern.with_seed(self.rng.next_u64());
+
+        new_pattern.enter();
+
         if let Some(transition) = transition {
             self.transition = Some(TransitionState {
                 driver: transition,
@@ -31,11 +198,17 @@ impl State {
         } else {
             self.current_start = time;
             self.transition = None;
+            self.pattern.exit();
             self.pattern = new_pattern;
         }
     }
 
-    pub fn drive(&mut self, time: u32, cube: &mut Cube) {
+    /// Draw the next frame. `wall_time` is the real time in ms, which is converted to pattern time
+    /// by the playback [`clock`](State::clock).
+    pub fn drive(&mut self, wall_time: u32, cube: &mut Cube) {
+        let floor = self.earliest_time();
+        let time = self.clock.tick(wall_time, floor);
+
         let pattern_run_time = time - self.current_start;
 
         if let Some(t) = self.transition.as_mut() {
@@ -63,6 +236,7 @@ impl State {
                     *current = new;
                 }
             } else {
+                self.pattern.exit();
                 self.pattern = t.next_pattern.clone();
                 self.current_start = next_start;
                 self.transition = None;
@@ -70,45 +244,68 @@ impl State {
         } else {
             cube.fill_iter(self.pattern.update_iter(pattern_run_time));
 
-            match self.pattern {
-                Pattern::Rainbow(ref mut pattern) => {
-                    if pattern.completed_cycles(pattern_run_time) >= 7 {
-                        self.next_pattern(
-                            time,
-                            Pattern::SlowRain(SlowRain::default()),
-                            Some(Transition::CrossFade(CrossFade::default())),
-                        );
-                    }
-                }
-                Pattern::SlowRain(ref mut pattern) => {
-                    // "cycles" doesn't mean a lot here as drops have different offsets
-                    if pattern.completed_cycles(pattern_run_time) == 10 {
-                        self.next_pattern(
-                            time,
-                            Pattern::Slices(Slices::default()),
-                            Some(Transition::FadeToBlack(FadeToBlack::default())),
-                        );
-                    }
-                }
-                Pattern::Slices(ref mut pattern) => {
-                    if pattern.completed_cycles(pattern_run_time) == 2 {
-                        self.next_pattern(
-                            time,
-                            Pattern::ChristmasPuke(ChristmasPuke::default()),
-                            Some(Transition::CrossFade(CrossFade::default())),
-                        );
-                    }
-                }
-                Pattern::ChristmasPuke(ref mut pattern) => {
-                    if pattern.completed_cycles(pattern_run_time) == 7 {
-                        self.next_pattern(
-                            time,
-                            Pattern::Rainbow(Rainbow::default()),
-                            Some(Transition::CrossFade(CrossFade::default())),
-                        );
-                    }
+            // Patterns that aren't part of the playlist run until another pattern is selected
+            if let Some(position) = self.playlist_position {
+                let entry = &PLAYLIST[position];
+
+                if is_complete(&mut self.pattern, entry.cycles, pattern_run_time) {
+                    let next = (position + 1) % PLAYLIST.len();
+
+                    self.playlist_position = Some(next);
+                    self.next_pattern(time, (PLAYLIST[next].pattern)(), Some((entry.transition)()));
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_frame(state: State) -> Cube {
+        let mut state = state;
+        let mut cube = Cube::new(255);
+
+        state.drive(0, &mut cube);
+
+        cube
+    }
+
+    #[test]
+    fn seeded_patterns() {
+        let rain = || State::new(Pattern::SlowRain(SlowRain::default()));
+
+        assert_eq!(
+            first_frame(rain().with_seed(1)).frame(),
+            first_frame(rain().with_seed(1)).frame()
+        );
+        assert_ne!(
+            first_frame(rain().with_seed(1)).frame(),
+            first_frame(rain().with_seed(2)).frame()
+        );
+    }
+
+    #[test]
+    fn playlist_advances_on_cycle_boundary() {
+        let mut state = State::new(Pattern::Rainbow(Rainbow::default()));
+        let mut cube = Cube::new(255);
+
+        assert_eq!(state.playlist_position, Some(0));
+
+        // Rainbow plays for 7 one second cycles
+        state.drive(6999, &mut cube);
+        assert!(state.transition.is_none());
+
+        state.drive(7000, &mut cube);
+        assert!(state.transition.is_some());
+        assert_eq!(state.playlist_position, Some(1));
+
+        // Not part of the playlist
+        let mut state = State::new(Pattern::Plasma(Plasma::default()));
+
+        state.drive(1_000_000, &mut cube);
+        assert_eq!(state.playlist_position, None);
+        assert!(state.transition.is_none());
+    }
+}
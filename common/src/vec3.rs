@@ -0,0 +1,196 @@
+use crate::voxel::Voxel;
+use core::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+#[cfg(not(test))]
+use micromath::F32Ext;
+
+/// Signed, floating point position or direction in cube space.
+///
+/// One unit is the distance between two adjacent voxels, so voxel centres lie at whole numbers
+/// between `0.0` and `3.0` inclusive.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Vec3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Vec3 {
+    pub const ZERO: Vec3 = Vec3::new(0.0, 0.0, 0.0);
+
+    /// Centre of the cube.
+    pub const CENTRE: Vec3 = Vec3::new(1.5, 1.5, 1.5);
+
+    pub const fn new(x: f32, y: f32, z: f32) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn dot(self, other: Self) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn length_squared(self) -> f32 {
+        self.dot(self)
+    }
+
+    pub fn length(self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    pub fn distance(self, other: Self) -> f32 {
+        (self - other).length()
+    }
+
+    pub fn distance_squared(self, other: Self) -> f32 {
+        (self - other).length_squared()
+    }
+
+    /// Unit vector in the same direction, or `None` for a zero length vector.
+    pub fn normalize(self) -> Option<Self> {
+        let length = self.length();
+
+        if length > 0.0 {
+            Some(self / length)
+        } else {
+            None
+        }
+    }
+
+    pub fn lerp(self, other: Self, ratio: f32) -> Self {
+        self + (other - self) * ratio
+    }
+
+    /// Multiply each component by the matching component of `other`.
+    pub fn scale(self, other: Self) -> Self {
+        Self::new(self.x * other.x, self.y * other.y, self.z * other.z)
+    }
+
+    pub fn is_in_bounds(self) -> bool {
+        let range = -0.5..3.5;
+
+        range.contains(&self.x) && range.contains(&self.y) && range.contains(&self.z)
+    }
+
+    /// Round to the nearest voxel, or `None` if the position lies outside the cube.
+    pub fn to_voxel(self) -> Option<Voxel> {
+        if self.is_in_bounds() {
+            Some(self.to_voxel_clamped())
+        } else {
+            None
+        }
+    }
+
+    /// Round to the nearest voxel, clamping positions outside the cube to its faces.
+    pub fn to_voxel_clamped(self) -> Voxel {
+        Voxel {
+            x: clamp_coord(self.x),
+            y: clamp_coord(self.y),
+            z: clamp_coord(self.z),
+        }
+    }
+}
+
+fn clamp_coord(value: f32) -> u8 {
+    value.round().clamp(0.0, 3.0) as u8
+}
+
+impl From<Voxel> for Vec3 {
+    fn from(voxel: Voxel) -> Self {
+        Self::new(voxel.x as f32, voxel.y as f32, voxel.z as f32)
+    }
+}
+
+impl Add for Vec3 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl AddAssign for Vec3 {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Vec3 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl SubAssign for Vec3 {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<f32> for Vec3 {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Self::new(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+impl Div<f32> for Vec3 {
+    type Output = Self;
+
+    fn div(self, rhs: f32) -> Self::Output {
+        Self::new(self.x / rhs, self.y / rhs, self.z / rhs)
+    }
+}
+
+impl Neg for Vec3 {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.x, -self.y, -self.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let a = Vec3::new(1.0, 2.0, 3.0);
+        let b = Vec3::new(0.5, -1.0, 2.0);
+
+        assert_eq!(a + b, Vec3::new(1.5, 1.0, 5.0));
+        assert_eq!(a - b, Vec3::new(0.5, 3.0, 1.0));
+        assert_eq!(a * 2.0, Vec3::new(2.0, 4.0, 6.0));
+        assert_eq!(-a, Vec3::new(-1.0, -2.0, -3.0));
+        assert_eq!(a.dot(b), 4.5);
+    }
+
+    #[test]
+    fn distance_and_normalize() {
+        assert_eq!(Vec3::ZERO.distance(Vec3::new(3.0, 0.0, 4.0)), 5.0);
+        assert_eq!(
+            Vec3::new(0.0, 0.0, 2.0).normalize(),
+            Some(Vec3::new(0.0, 0.0, 1.0))
+        );
+        assert_eq!(Vec3::ZERO.normalize(), None);
+    }
+
+    #[test]
+    fn voxel_conversion() {
+        let voxel = Voxel { x: 1, y: 2, z: 3 };
+
+        assert_eq!(Vec3::from(voxel).to_voxel(), Some(voxel));
+        assert_eq!(
+            Vec3::new(1.4, 1.6, 0.2).to_voxel(),
+            Some(Voxel { x: 1, y: 2, z: 0 })
+        );
+        assert_eq!(Vec3::new(3.6, 0.0, 0.0).to_voxel(), None);
+        assert_eq!(Vec3::new(-0.6, 0.0, 0.0).to_voxel(), None);
+        assert_eq!(
+            Vec3::new(5.0, -2.0, 1.0).to_voxel_clamped(),
+            Voxel { x: 3, y: 0, z: 1 }
+        );
+    }
+}
@@ -0,0 +1,285 @@
+//! Assembler for VM programs.
+//!
+//! Source is a list of whitespace separated instructions. A bare number pushes that value, and
+//! every other instruction is written as its [mnemonic](super::Op::mnemonic). `name:` defines a
+//! label that `jmp` and `jz` can jump to, and `;` starts a comment that runs to the end of the
+//! line:
+//!
+//! ```text
+//! ; Red for the first second, then a slow rainbow
+//!     t 1000 lt jz rainbow
+//!     1 0 0 rgb
+//! rainbow:
+//!     t 5000 div  z 8 div  add  1 1 hsv
+//! ```
+
+use super::Op;
+use core::fmt;
+
+/// Largest number of labels in a program.
+pub const MAX_LABELS: usize = 16;
+
+/// Error produced when assembling a program. Line numbers start at 1.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AsmError<'a> {
+    /// Token isn't a number, label or known instruction.
+    UnknownInstruction { line: usize, token: &'a str },
+
+    /// Instruction at the end of the program is missing its operand.
+    MissingOperand { line: usize, token: &'a str },
+
+    /// Jump target isn't a defined label.
+    UnknownLabel { line: usize, label: &'a str },
+
+    /// Label is defined more than once.
+    DuplicateLabel { line: usize, label: &'a str },
+
+    /// Program defines more than [`MAX_LABELS`] labels.
+    TooManyLabels { line: usize },
+
+    /// Program doesn't fit in the output buffer, or is too long to address with a jump.
+    OutputFull,
+}
+
+impl fmt::Display for AsmError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownInstruction { line, token } => {
+                write!(f, "line {}: unknown instruction '{}'", line, token)
+            }
+            Self::MissingOperand { line, token } => {
+                write!(f, "line {}: '{}' is missing its operand", line, token)
+            }
+            Self::UnknownLabel { line, label } => {
+                write!(f, "line {}: unknown label '{}'", line, label)
+            }
+            Self::DuplicateLabel { line, label } => {
+                write!(f, "line {}: label '{}' is already defined", line, label)
+            }
+            Self::TooManyLabels { line } => write!(
+                f,
+                "line {}: too many labels, programs can have up to {}",
+                line, MAX_LABELS
+            ),
+            Self::OutputFull => write!(f, "program is too long"),
+        }
+    }
+}
+
+/// Iterate over the tokens in a program with their line numbers.
+fn tokens(src: &str) -> impl Iterator<Item = (usize, &str)> {
+    src.lines().enumerate().flat_map(|(idx, line)| {
+        let code = line.split(';').next().unwrap_or("");
+
+        code.split_whitespace().map(move |token| (idx + 1, token))
+    })
+}
+
+fn find_op(token: &str) -> Option<Op> {
+    Op::ALL.iter().copied().find(|op| op.mnemonic() == token)
+}
+
+struct Labels<'a> {
+    labels: [(&'a str, u16); MAX_LABELS],
+    len: usize,
+}
+
+impl<'a> Labels<'a> {
+    fn get(&self, name: &str) -> Option<u16> {
+        self.labels[0..self.len]
+            .iter()
+            .find(|(label, _)| *label == name)
+            .map(|(_, address)| *address)
+    }
+}
+
+/// Assemble a program into `out`, returning the length of the bytecode.
+pub fn assemble<'a>(src: &'a str, out: &mut [u8]) -> Result<usize, AsmError<'a>> {
+    // First pass finds the address of every label so jumps can refer to labels further on
+    let mut labels = Labels {
+        labels: [("", 0); MAX_LABELS],
+        len: 0,
+    };
+
+    let mut address = 0;
+    let mut iter = tokens(src);
+
+    while let Some((line, token)) = iter.next() {
+        if let Some(label) = token.strip_suffix(':') {
+            if labels.get(label).is_some() {
+                return Err(AsmError::DuplicateLabel { line, label });
+            }
+
+            let slot = labels
+                .labels
+                .get_mut(labels.len)
+                .ok_or(AsmError::TooManyLabels { line })?;
+
+            *slot = (label, address as u16);
+            labels.len += 1;
+
+            continue;
+        }
+
+        // Bare numbers carry their own value. Other operands are the following token.
+        let op = match token.parse::<f32>() {
+            Ok(_) => Op::Const,
+            Err(_) => {
+                let op = find_op(token).ok_or(AsmError::UnknownInstruction { line, token })?;
+
+                if op.operand_len() > 0 {
+                    iter.next()
+                        .ok_or(AsmError::MissingOperand { line, token })?;
+                }
+
+                op
+            }
+        };
+
+        address += 1 + op.operand_len();
+
+        if address > u16::MAX as usize {
+            return Err(AsmError::OutputFull);
+        }
+    }
+
+    // Second pass emits the bytecode
+    let mut len = 0;
+    let mut iter = tokens(src);
+
+    let mut emit = |bytes: &[u8]| -> Result<(), AsmError<'a>> {
+        out.get_mut(len..len + bytes.len())
+            .ok_or(AsmError::OutputFull)?
+            .copy_from_slice(bytes);
+
+        len += bytes.len();
+
+        Ok(())
+    };
+
+    while let Some((line, token)) = iter.next() {
+        if token.ends_with(':') {
+            continue;
+        }
+
+        let (op, operand) = match token.parse::<f32>() {
+            Ok(value) => (Op::Const, Some(value)),
+            Err(_) => (
+                find_op(token).ok_or(AsmError::UnknownInstruction { line, token })?,
+                None,
+            ),
+        };
+
+        emit(&[op as u8])?;
+
+        match op {
+            Op::Const => {
+                let value = match operand {
+                    Some(value) => value,
+                    // `push` with an explicit operand
+                    None => {
+                        let (line, value) = iter
+                            .next()
+                            .ok_or(AsmError::MissingOperand { line, token })?;
+
+                        value
+                            .parse::<f32>()
+                            .map_err(|_| AsmError::UnknownInstruction { line, token: value })?
+                    }
+                };
+
+                emit(&value.to_le_bytes())?;
+            }
+            Op::Jmp | Op::Jz => {
+                let (line, label) = iter
+                    .next()
+                    .ok_or(AsmError::MissingOperand { line, token })?;
+
+                let target = labels
+                    .get(label)
+                    .ok_or(AsmError::UnknownLabel { line, label })?;
+
+                emit(&target.to_le_bytes())?;
+            }
+            _ => (),
+        }
+    }
+
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoding() {
+        let mut out = [0; 32];
+
+        let len = assemble(
+            "start: x 1.5 push 2 ; comment
+             jz start rgb",
+            &mut out,
+        )
+        .unwrap();
+
+        let one_half = 1.5f32.to_le_bytes();
+        let two = 2.0f32.to_le_bytes();
+
+        assert_eq!(
+            out[0..len],
+            [
+                Op::X as u8,
+                Op::Const as u8,
+                one_half[0],
+                one_half[1],
+                one_half[2],
+                one_half[3],
+                Op::Const as u8,
+                two[0],
+                two[1],
+                two[2],
+                two[3],
+                Op::Jz as u8,
+                0,
+                0,
+                Op::Rgb as u8,
+            ]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let mut out = [0; 8];
+
+        assert_eq!(
+            assemble("x\n  y wat", &mut out),
+            Err(AsmError::UnknownInstruction {
+                line: 2,
+                token: "wat"
+            })
+        );
+        assert_eq!(
+            assemble("jmp nowhere", &mut out),
+            Err(AsmError::UnknownLabel {
+                line: 1,
+                label: "nowhere"
+            })
+        );
+        assert_eq!(
+            assemble("a: a:", &mut out),
+            Err(AsmError::DuplicateLabel {
+                line: 1,
+                label: "a"
+            })
+        );
+        assert_eq!(
+            assemble("x jz", &mut out),
+            Err(AsmError::MissingOperand {
+                line: 1,
+                token: "jz"
+            })
+        );
+        assert_eq!(assemble("1 2", &mut out), Err(AsmError::OutputFull));
+    }
+}
@@ -0,0 +1,469 @@
+//! A tiny sandboxed stack machine for patterns loaded as data.
+//!
+//! A program computes the colour of one voxel from its position and the current time. Every run
+//! is limited to a fixed number of instructions and a small stack, so a broken or malicious
+//! program can't hang or crash the firmware. Programs are written in a simple assembly language
+//! and turned into bytecode with [`assemble`], which needs no allocator so it runs on the firmware
+//! as well as the simulator.
+//!
+//! Values are all `f32`. Comparisons push `1.0` for true and `0.0` for false.
+
+mod assembler;
+
+pub use assembler::*;
+
+use crate::apa106led::Apa106Led;
+use core::fmt;
+#[cfg(not(test))]
+use micromath::F32Ext;
+
+/// Largest number of values on the stack.
+pub const STACK_SIZE: usize = 16;
+
+/// Default number of instructions a program may run per voxel.
+pub const DEFAULT_BUDGET: u32 = 256;
+
+/// Largest number of instructions a program may run per voxel.
+///
+/// Every voxel is drawn from the frame interrupt, so a frame runs up to 64 times this many
+/// instructions. A 33 ms frame on the 72 MHz, FPU-less firmware is about 37,000 cycles per voxel,
+/// which leaves room for around 512 soft float instructions.
+pub const MAX_BUDGET: u32 = 512;
+
+/// A single VM instruction.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[repr(u8)]
+pub enum Op {
+    /// Push the `f32` stored in the next 4 bytes, little endian.
+    Const = 0x01,
+
+    /// Push the voxel X coordinate, 0.0 - 3.0.
+    X = 0x02,
+
+    /// Push the voxel Y coordinate, 0.0 - 3.0.
+    Y = 0x03,
+
+    /// Push the voxel Z coordinate, 0.0 - 3.0.
+    Z = 0x04,
+
+    /// Push the time since the pattern started, ms.
+    T = 0x05,
+
+    Dup = 0x10,
+    Swap = 0x11,
+    Pop = 0x12,
+
+    Add = 0x20,
+    Sub = 0x21,
+    Mul = 0x22,
+    Div = 0x23,
+
+    /// Remainder with the sign of the divisor, so the result is always positive for a positive
+    /// divisor.
+    Mod = 0x24,
+    Neg = 0x25,
+    Abs = 0x26,
+    Min = 0x27,
+    Max = 0x28,
+    Floor = 0x29,
+    Sin = 0x2a,
+    Cos = 0x2b,
+    Sqrt = 0x2c,
+
+    /// Pop `b` then `a` and push whether `a < b`.
+    Lt = 0x30,
+
+    /// Pop `b` then `a` and push whether `a > b`.
+    Gt = 0x31,
+
+    /// Jump to the address stored in the next 2 bytes, little endian.
+    Jmp = 0x40,
+
+    /// Pop a value and jump to the address stored in the next 2 bytes if it's zero.
+    Jz = 0x41,
+
+    /// Pop blue, green then red, each 0.0 - 1.0, and finish with that colour.
+    Rgb = 0x50,
+
+    /// Pop value, saturation then hue and finish with that colour. See
+    /// [`Apa106Led::from_hsv`].
+    Hsv = 0x51,
+}
+
+impl Op {
+    /// Every instruction, for lookups by byte or mnemonic.
+    pub const ALL: [Op; 27] = [
+        Op::Const,
+        Op::X,
+        Op::Y,
+        Op::Z,
+        Op::T,
+        Op::Dup,
+        Op::Swap,
+        Op::Pop,
+        Op::Add,
+        Op::Sub,
+        Op::Mul,
+        Op::Div,
+        Op::Mod,
+        Op::Neg,
+        Op::Abs,
+        Op::Min,
+        Op::Max,
+        Op::Floor,
+        Op::Sin,
+        Op::Cos,
+        Op::Sqrt,
+        Op::Lt,
+        Op::Gt,
+        Op::Jmp,
+        Op::Jz,
+        Op::Rgb,
+        Op::Hsv,
+    ];
+
+    /// Decode an opcode, or `None` if the byte isn't a valid instruction.
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        Self::ALL.iter().copied().find(|op| *op as u8 == byte)
+    }
+
+    /// Assembly language name of this instruction.
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Self::Const => "push",
+            Self::X => "x",
+            Self::Y => "y",
+            Self::Z => "z",
+            Self::T => "t",
+            Self::Dup => "dup",
+            Self::Swap => "swap",
+            Self::Pop => "pop",
+            Self::Add => "add",
+            Self::Sub => "sub",
+            Self::Mul => "mul",
+            Self::Div => "div",
+            Self::Mod => "mod",
+            Self::Neg => "neg",
+            Self::Abs => "abs",
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::Floor => "floor",
+            Self::Sin => "sin",
+            Self::Cos => "cos",
+            Self::Sqrt => "sqrt",
+            Self::Lt => "lt",
+            Self::Gt => "gt",
+            Self::Jmp => "jmp",
+            Self::Jz => "jz",
+            Self::Rgb => "rgb",
+            Self::Hsv => "hsv",
+        }
+    }
+
+    /// Number of operand bytes following the opcode.
+    pub fn operand_len(self) -> usize {
+        match self {
+            Self::Const => 4,
+            Self::Jmp | Self::Jz => 2,
+            _ => 0,
+        }
+    }
+}
+
+/// Error produced when running a program.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum VmError {
+    /// Byte at this address isn't a valid instruction.
+    InvalidOpcode { address: usize, opcode: u8 },
+
+    /// Program ended, either in the middle of an instruction or without producing a colour.
+    UnexpectedEnd,
+
+    /// Jump target lies outside the program.
+    JumpOutOfRange { address: usize, target: u16 },
+
+    /// Tried to push more than [`STACK_SIZE`] values.
+    StackOverflow { address: usize },
+
+    /// Tried to pop from an empty stack.
+    StackUnderflow { address: usize },
+
+    /// Program didn't finish within its instruction budget.
+    BudgetExceeded,
+
+    /// Program is too long to be loaded.
+    ProgramTooLong(usize),
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidOpcode { address, opcode } => {
+                write!(f, "invalid opcode {:#04x} at address {}", opcode, address)
+            }
+            Self::UnexpectedEnd => write!(f, "program ended without producing a colour"),
+            Self::JumpOutOfRange { address, target } => write!(
+                f,
+                "jump at address {} to {} lies outside the program",
+                address, target
+            ),
+            Self::StackOverflow { address } => write!(
+                f,
+                "stack overflow at address {}, the stack holds {} values",
+                address, STACK_SIZE
+            ),
+            Self::StackUnderflow { address } => write!(f, "stack underflow at address {}", address),
+            Self::BudgetExceeded => {
+                write!(f, "program didn't finish within its instruction budget")
+            }
+            Self::ProgramTooLong(len) => write!(f, "program is too long at {} bytes", len),
+        }
+    }
+}
+
+/// Values a program can read.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Inputs {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+
+    /// Time since the pattern started, ms.
+    pub t: f32,
+}
+
+struct Stack {
+    values: [f32; STACK_SIZE],
+    len: usize,
+}
+
+impl Stack {
+    fn push(&mut self, value: f32, address: usize) -> Result<(), VmError> {
+        let slot = self
+            .values
+            .get_mut(self.len)
+            .ok_or(VmError::StackOverflow { address })?;
+
+        *slot = value;
+        self.len += 1;
+
+        Ok(())
+    }
+
+    fn pop(&mut self, address: usize) -> Result<f32, VmError> {
+        if self.len == 0 {
+            return Err(VmError::StackUnderflow { address });
+        }
+
+        self.len -= 1;
+
+        Ok(self.values[self.len])
+    }
+}
+
+fn read_operand<const N: usize>(code: &[u8], start: usize) -> Result<[u8; N], VmError> {
+    let mut bytes = [0; N];
+
+    bytes.copy_from_slice(code.get(start..start + N).ok_or(VmError::UnexpectedEnd)?);
+
+    Ok(bytes)
+}
+
+/// Run a program for a single voxel, executing at most `budget` instructions.
+pub fn run(code: &[u8], inputs: Inputs, budget: u32) -> Result<Apa106Led, VmError> {
+    let mut stack = Stack {
+        values: [0.0; STACK_SIZE],
+        len: 0,
+    };
+
+    let mut pc = 0;
+
+    for _ in 0..budget {
+        let address = pc;
+
+        let byte = *code.get(pc).ok_or(VmError::UnexpectedEnd)?;
+        let op = Op::from_byte(byte).ok_or(VmError::InvalidOpcode {
+            address,
+            opcode: byte,
+        })?;
+
+        pc += 1 + op.operand_len();
+
+        let unary = |stack: &mut Stack, f: fn(f32) -> f32| -> Result<(), VmError> {
+            let a = stack.pop(address)?;
+
+            stack.push(f(a), address)
+        };
+
+        let binary = |stack: &mut Stack, f: fn(f32, f32) -> f32| -> Result<(), VmError> {
+            let b = stack.pop(address)?;
+            let a = stack.pop(address)?;
+
+            stack.push(f(a, b), address)
+        };
+
+        match op {
+            Op::Const => {
+                let value = f32::from_le_bytes(read_operand(code, address + 1)?);
+
+                stack.push(value, address)?
+            }
+            Op::X => stack.push(inputs.x, address)?,
+            Op::Y => stack.push(inputs.y, address)?,
+            Op::Z => stack.push(inputs.z, address)?,
+            Op::T => stack.push(inputs.t, address)?,
+            Op::Dup => {
+                let a = stack.pop(address)?;
+
+                stack.push(a, address)?;
+                stack.push(a, address)?;
+            }
+            Op::Swap => {
+                let b = stack.pop(address)?;
+                let a = stack.pop(address)?;
+
+                stack.push(b, address)?;
+                stack.push(a, address)?;
+            }
+            Op::Pop => {
+                stack.pop(address)?;
+            }
+            Op::Add => binary(&mut stack, |a, b| a + b)?,
+            Op::Sub => binary(&mut stack, |a, b| a - b)?,
+            Op::Mul => binary(&mut stack, |a, b| a * b)?,
+            Op::Div => binary(&mut stack, |a, b| a / b)?,
+            Op::Mod => binary(&mut stack, |a, b| a - b * (a / b).floor())?,
+            Op::Neg => unary(&mut stack, |a| -a)?,
+            Op::Abs => unary(&mut stack, |a| a.abs())?,
+            Op::Min => binary(&mut stack, |a, b| a.min(b))?,
+            Op::Max => binary(&mut stack, |a, b| a.max(b))?,
+            Op::Floor => unary(&mut stack, |a| a.floor())?,
+            Op::Sin => unary(&mut stack, |a| a.sin())?,
+            Op::Cos => unary(&mut stack, |a| a.cos())?,
+            Op::Sqrt => unary(&mut stack, |a| a.max(0.0).sqrt())?,
+            Op::Lt => binary(&mut stack, |a, b| if a < b { 1.0 } else { 0.0 })?,
+            Op::Gt => binary(&mut stack, |a, b| if a > b { 1.0 } else { 0.0 })?,
+            Op::Jmp | Op::Jz => {
+                let target = u16::from_le_bytes(read_operand(code, address + 1)?);
+
+                if target as usize >= code.len() {
+                    return Err(VmError::JumpOutOfRange { address, target });
+                }
+
+                let jump = match op {
+                    Op::Jz => stack.pop(address)? == 0.0,
+                    _ => true,
+                };
+
+                if jump {
+                    pc = target as usize;
+                }
+            }
+            Op::Rgb => {
+                let blue = stack.pop(address)?;
+                let green = stack.pop(address)?;
+                let red = stack.pop(address)?;
+
+                let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0) as u8;
+
+                return Ok(Apa106Led {
+                    red: channel(red),
+                    green: channel(green),
+                    blue: channel(blue),
+                });
+            }
+            Op::Hsv => {
+                let value = stack.pop(address)?;
+                let saturation = stack.pop(address)?;
+                let hue = stack.pop(address)?;
+
+                return Ok(Apa106Led::from_hsv(hue, saturation, value));
+            }
+        }
+    }
+
+    Err(VmError::BudgetExceeded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUTS: Inputs = Inputs {
+        x: 1.0,
+        y: 2.0,
+        z: 3.0,
+        t: 500.0,
+    };
+
+    fn run_asm(src: &str) -> Result<Apa106Led, VmError> {
+        let mut code = [0; 128];
+
+        let len = assemble(src, &mut code).unwrap();
+
+        run(&code[0..len], INPUTS, DEFAULT_BUDGET)
+    }
+
+    #[test]
+    fn opcodes_round_trip() {
+        for op in Op::ALL.iter() {
+            assert_eq!(Op::from_byte(*op as u8), Some(*op));
+        }
+
+        assert_eq!(Op::from_byte(0xff), None);
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(
+            run_asm("x 3 div  y 4 div  z 1 add 4 div  rgb"),
+            Ok(Apa106Led {
+                red: 85,
+                green: 127,
+                blue: 255
+            })
+        );
+        assert_eq!(run_asm("-1 4 mod 3 div 0 0 rgb").unwrap().red, 255);
+    }
+
+    #[test]
+    fn branches() {
+        let src = "
+            t 1000 lt jz dark
+            1 1 1 rgb
+        dark:
+            0 0 0 rgb
+        ";
+
+        assert_eq!(run_asm(src).unwrap().red, 255);
+    }
+
+    #[test]
+    fn sandboxed() {
+        assert_eq!(run_asm("loop: jmp loop"), Err(VmError::BudgetExceeded));
+        assert_eq!(run_asm("add"), Err(VmError::StackUnderflow { address: 0 }));
+        assert_eq!(run_asm("1 2"), Err(VmError::UnexpectedEnd));
+        assert_eq!(
+            run_asm("loop: 1 jmp loop"),
+            Err(VmError::StackOverflow { address: 0 })
+        );
+        assert_eq!(
+            run(&[0xff], INPUTS, DEFAULT_BUDGET),
+            Err(VmError::InvalidOpcode {
+                address: 0,
+                opcode: 0xff
+            })
+        );
+        assert_eq!(
+            run(&[Op::Jmp as u8, 0xff, 0x00], INPUTS, DEFAULT_BUDGET),
+            Err(VmError::JumpOutOfRange {
+                address: 0,
+                target: 0xff
+            })
+        );
+        assert_eq!(
+            run(&[Op::Const as u8, 0, 0], INPUTS, DEFAULT_BUDGET),
+            Err(VmError::UnexpectedEnd)
+        );
+    }
+}
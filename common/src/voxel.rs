@@ -1,3 +1,80 @@
+/// Error produced when converting an out of range coordinate or index into a [`Voxel`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum VoxelError {
+    /// One or more coordinates lie outside the range `0..=3`.
+    CoordOutOfRange { x: u8, y: u8, z: u8 },
+
+    /// Frame index lies outside the range `0..=63`.
+    IndexOutOfRange(usize),
+}
+
+/// One of the three axes of the cube.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Axis {
+    /// Left to right
+    X,
+
+    /// Front to back
+    Y,
+
+    /// Vertical
+    Z,
+}
+
+impl Axis {
+    /// Cycle through X, Y and Z.
+    pub fn next(self) -> Self {
+        match self {
+            Self::X => Self::Y,
+            Self::Y => Self::Z,
+            Self::Z => Self::X,
+        }
+    }
+
+    /// Position of a voxel along this axis.
+    pub fn coord(self, voxel: Voxel) -> u8 {
+        match self {
+            Self::X => voxel.x,
+            Self::Y => voxel.y,
+            Self::Z => voxel.z,
+        }
+    }
+}
+
+/// Which surrounding voxels count as neighbours.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Connectivity {
+    /// Voxels sharing a face.
+    Six,
+
+    /// Voxels sharing a face or an edge.
+    Eighteen,
+
+    /// Voxels sharing a face, an edge or a corner.
+    TwentySix,
+}
+
+impl Connectivity {
+    /// Maximum number of axes a neighbour may be offset along.
+    fn max_axes(self) -> u8 {
+        match self {
+            Self::Six => 1,
+            Self::Eighteen => 2,
+            Self::TwentySix => 3,
+        }
+    }
+}
+
+/// How neighbour queries treat the faces of the cube.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Edges {
+    /// Voxels on the faces of the cube have fewer neighbours.
+    Bounded,
+
+    /// Neighbours wrap around to the opposite face, treating the cube as a 3D torus.
+    Wrap,
+}
+
 #[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub struct Voxel {
     pub x: u8,
@@ -6,7 +83,58 @@ pub struct Voxel {
 }
 
 impl Voxel {
-    pub fn from_index(idx: usize) -> Self {
+    /// Create a voxel, checking that it lies inside the cube.
+    pub fn new(x: u8, y: u8, z: u8) -> Result<Self, VoxelError> {
+        let voxel = Self { x, y, z };
+
+        if voxel.is_in_bounds() {
+            Ok(voxel)
+        } else {
+            Err(VoxelError::CoordOutOfRange { x, y, z })
+        }
+    }
+
+    pub fn is_in_bounds(&self) -> bool {
+        self.x < 4 && self.y < 4 && self.z < 4
+    }
+
+    /// Iterate over the neighbours of this voxel.
+    pub fn neighbours(self, connectivity: Connectivity, edges: Edges) -> Neighbours {
+        Neighbours {
+            origin: self,
+            connectivity,
+            edges,
+            offset_idx: 0,
+        }
+    }
+
+    /// Sum of the distances along each axis.
+    pub fn manhattan_distance(self, other: Self) -> u8 {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y) + self.z.abs_diff(other.z)
+    }
+
+    /// Largest distance along any one axis.
+    pub fn chebyshev_distance(self, other: Self) -> u8 {
+        self.x
+            .abs_diff(other.x)
+            .max(self.y.abs_diff(other.y))
+            .max(self.z.abs_diff(other.z))
+    }
+
+    pub fn try_from_index(idx: usize) -> Result<Self, VoxelError> {
+        if idx < 64 {
+            Ok(Self::from_index_unchecked(idx))
+        } else {
+            Err(VoxelError::IndexOutOfRange(idx))
+        }
+    }
+
+    /// Convert a frame index into a voxel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is larger than 63. Use [`Voxel::try_from_index`] for untrusted input.
+    pub fn from_index_unchecked(idx: usize) -> Self {
         let z = idx / 16;
 
         let remaining = idx % 16;
@@ -42,7 +170,29 @@ impl Voxel {
         }
     }
 
-    pub fn into_index(self) -> usize {
+    pub fn try_into_index(self) -> Result<usize, VoxelError> {
+        if self.is_in_bounds() {
+            Ok(self.into_index_unchecked())
+        } else {
+            Err(VoxelError::CoordOutOfRange {
+                x: self.x,
+                y: self.y,
+                z: self.z,
+            })
+        }
+    }
+
+    /// Convert this voxel into a frame index.
+    ///
+    /// # Panics
+    ///
+    /// May panic or return an incorrect index if any coordinate is larger than 3. Use
+    /// [`Voxel::try_into_index`] for untrusted input.
+    pub fn into_index_unchecked(self) -> usize {
+        debug_assert!(self.x < 4, "X coordinate {} out of range", self.x);
+        debug_assert!(self.y < 4, "Y coordinate {} out of range", self.y);
+        debug_assert!(self.z < 4, "Z coordinate {} out of range", self.z);
+
         let index = match self.z {
             0 | 2 => match self.y {
                 0 | 2 => (4 * self.y) + self.x,
@@ -64,13 +214,76 @@ impl Voxel {
     }
 }
 
+/// Iterator over the neighbours of a voxel, created by [`Voxel::neighbours`].
+#[derive(Clone, Debug)]
+pub struct Neighbours {
+    origin: Voxel,
+    connectivity: Connectivity,
+    edges: Edges,
+
+    /// Position in the 3x3x3 block of offsets surrounding the origin.
+    offset_idx: u8,
+}
+
+impl Neighbours {
+    fn offset(&self, dx: i8, dy: i8, dz: i8) -> Option<Voxel> {
+        Some(Voxel {
+            x: self.offset_coord(self.origin.x, dx)?,
+            y: self.offset_coord(self.origin.y, dy)?,
+            z: self.offset_coord(self.origin.z, dz)?,
+        })
+    }
+
+    fn offset_coord(&self, coord: u8, offset: i8) -> Option<u8> {
+        // Widen first, as unchecked voxels can hold coordinates that don't fit in an i8
+        let coord = coord as i16 + offset as i16;
+
+        match self.edges {
+            Edges::Bounded if (0..4).contains(&coord) => Some(coord as u8),
+            Edges::Bounded => None,
+            Edges::Wrap => Some(coord.rem_euclid(4) as u8),
+        }
+    }
+}
+
+impl Iterator for Neighbours {
+    type Item = Voxel;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.offset_idx < 27 {
+            let idx = self.offset_idx;
+
+            self.offset_idx += 1;
+
+            let dx = (idx % 3) as i8 - 1;
+            let dy = ((idx / 3) % 3) as i8 - 1;
+            let dz = (idx / 9) as i8 - 1;
+
+            let axes = (dx != 0) as u8 + (dy != 0) as u8 + (dz != 0) as u8;
+
+            // Skip the origin itself and any offsets not matching the connectivity
+            if axes == 0 || axes > self.connectivity.max_axes() {
+                continue;
+            }
+
+            let neighbour = self.offset(dx, dy, dz);
+
+            if neighbour.is_some() {
+                return neighbour;
+            }
+        }
+
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     fn rt(voxel: Voxel, index: usize) {
-        assert_eq!(voxel.into_index(), index, "voxel {:?}", voxel);
-        assert_eq!(Voxel::from_index(index), voxel, "index {}", index);
+        assert_eq!(voxel.into_index_unchecked(), index, "voxel {:?}", voxel);
+        assert_eq!(Voxel::from_index_unchecked(index), voxel, "index {}", index);
     }
 
     #[test]
@@ -92,4 +305,77 @@ mod tests {
         rt(Voxel { x: 1, y: 2, z: 3 }, 54);
         rt(Voxel { x: 3, y: 3, z: 2 }, 44);
     }
+
+    #[test]
+    fn checked() {
+        assert_eq!(Voxel::new(1, 2, 3), Ok(Voxel { x: 1, y: 2, z: 3 }));
+        assert_eq!(
+            Voxel::new(4, 0, 0),
+            Err(VoxelError::CoordOutOfRange { x: 4, y: 0, z: 0 })
+        );
+
+        assert_eq!(Voxel::try_from_index(51), Ok(Voxel { x: 3, y: 3, z: 3 }));
+        assert_eq!(
+            Voxel::try_from_index(64),
+            Err(VoxelError::IndexOutOfRange(64))
+        );
+
+        assert_eq!(Voxel { x: 1, y: 2, z: 3 }.try_into_index(), Ok(54));
+        assert_eq!(
+            Voxel { x: 0, y: 9, z: 0 }.try_into_index(),
+            Err(VoxelError::CoordOutOfRange { x: 0, y: 9, z: 0 })
+        );
+    }
+
+    #[test]
+    fn neighbour_counts() {
+        let corner = Voxel { x: 0, y: 0, z: 0 };
+        let inside = Voxel { x: 1, y: 2, z: 1 };
+
+        let count =
+            |voxel: Voxel, connectivity, edges| voxel.neighbours(connectivity, edges).count();
+
+        assert_eq!(count(corner, Connectivity::Six, Edges::Bounded), 3);
+        assert_eq!(count(corner, Connectivity::Eighteen, Edges::Bounded), 6);
+        assert_eq!(count(corner, Connectivity::TwentySix, Edges::Bounded), 7);
+
+        assert_eq!(count(inside, Connectivity::Six, Edges::Bounded), 6);
+        assert_eq!(count(inside, Connectivity::Eighteen, Edges::Bounded), 18);
+        assert_eq!(count(inside, Connectivity::TwentySix, Edges::Bounded), 26);
+
+        assert_eq!(count(corner, Connectivity::Six, Edges::Wrap), 6);
+        assert_eq!(count(corner, Connectivity::Eighteen, Edges::Wrap), 18);
+        assert_eq!(count(corner, Connectivity::TwentySix, Edges::Wrap), 26);
+
+        // Unchecked voxels far outside the cube mustn't overflow
+        let outside = Voxel { x: 200, y: 0, z: 0 };
+
+        assert_eq!(count(outside, Connectivity::TwentySix, Edges::Bounded), 0);
+        assert_eq!(count(outside, Connectivity::Six, Edges::Wrap), 6);
+    }
+
+    #[test]
+    fn wrapped_neighbours() {
+        let corner = Voxel { x: 0, y: 0, z: 0 };
+
+        let mut neighbours = corner.neighbours(Connectivity::Six, Edges::Wrap);
+
+        assert_eq!(neighbours.next(), Some(Voxel { x: 0, y: 0, z: 3 }));
+        assert_eq!(neighbours.next(), Some(Voxel { x: 0, y: 3, z: 0 }));
+        assert_eq!(neighbours.next(), Some(Voxel { x: 3, y: 0, z: 0 }));
+        assert_eq!(neighbours.next(), Some(Voxel { x: 1, y: 0, z: 0 }));
+        assert_eq!(neighbours.next(), Some(Voxel { x: 0, y: 1, z: 0 }));
+        assert_eq!(neighbours.next(), Some(Voxel { x: 0, y: 0, z: 1 }));
+        assert_eq!(neighbours.next(), None);
+    }
+
+    #[test]
+    fn distances() {
+        let a = Voxel { x: 0, y: 1, z: 3 };
+        let b = Voxel { x: 2, y: 1, z: 0 };
+
+        assert_eq!(a.manhattan_distance(b), 5);
+        assert_eq!(a.chebyshev_distance(b), 3);
+        assert_eq!(a.manhattan_distance(a), 0);
+    }
 }
@@ -5,6 +5,7 @@ use core::ptr;
 
 use common::{
     apa106led::Apa106Led,
+    calibration::Calibration,
     cube::Cube,
     patterns::{Pattern, Rainbow},
 };
@@ -12,9 +13,10 @@ use cortex_m::singleton;
 use firmware as _; // global logger + panicking-behavior + memory layout
 use rtic::app;
 use stm32f1xx_hal::{
+    adc::{Adc, SampleTime},
     dma::WriteDma,
     gpio::{self, gpioc::PC13, Output, PushPull},
-    pac::{self, SPI2},
+    pac::{self, ADC1, SPI2},
     prelude::*,
     spi::{Mode, Phase, Polarity, Spi, SpiTxDma},
     timer::{CountDownTimer, Event, Timer},
@@ -38,6 +40,44 @@ type DmaInterface = SpiTxDma<
 // 1000 / FPS should produce an integer for better accuracy.
 const FPS: u32 = 30;
 
+// Global brightness, 0 - 255. Set at build time as the cube has no input to change it with.
+const BRIGHTNESS: u8 = 105;
+
+// Pattern playback speed. 1.0 is real time, negative values play backwards.
+const TIME_SCALE: f32 = 1.0;
+
+// Per-LED colour correction. Generate a table on the host with `Calibration::to_bytes` and bake it
+// in with `Calibration::from_bytes(include_bytes!("../../calibration.bin"))`.
+const CALIBRATION: Calibration = Calibration::IDENTITY;
+
+// Address of the 96 bit factory programmed unique device ID.
+const UNIQUE_ID: *const u32 = 0x1fff_f7e8 as *const u32;
+
+/// Build an RNG seed that's different every boot, so the cube doesn't play the same sequence each
+/// time it's switched on.
+///
+/// The lowest bit of a fast ADC conversion of the internal reference is mostly noise, so 64 of them
+/// are collected and combined with the chip's unique ID. The ID alone is constant, but stops two
+/// cubes that happen to sample the same noise from playing in sync.
+fn boot_seed(adc: &mut Adc<ADC1>) -> u64 {
+    let id = unsafe {
+        [
+            ptr::read_volatile(UNIQUE_ID),
+            ptr::read_volatile(UNIQUE_ID.add(1)),
+            ptr::read_volatile(UNIQUE_ID.add(2)),
+        ]
+    };
+
+    let id = (id[0] as u64 | ((id[1] as u64) << 32)) ^ (id[2] as u64).rotate_left(16);
+
+    // Shortest sample time gives the noisiest readings
+    adc.set_sample_time(SampleTime::T_1);
+
+    let noise = (0..64).fold(0u64, |noise, _| (noise << 1) | (adc.read_vref() & 1) as u64);
+
+    id ^ noise
+}
+
 #[app(device = stm32f1xx_hal::stm32, peripherals = true, monotonic = rtic::cyccnt::CYCCNT)]
 const APP: () = {
     struct Resources {
@@ -114,7 +154,9 @@ const APP: () = {
         // let buf = singleton!(: [u8; DATA_LEN] = [OFF_BYTE; DATA_LEN]).unwrap();
         // unsafe { DATA[DATA.len() - 1] = 0x00 };
 
-        let mut cube = Cube::new(8);
+        let mut cube = Cube::new(BRIGHTNESS);
+
+        cube.calibration = CALIBRATION;
 
         cube.fill(Apa106Led {
             red: 2,
@@ -122,7 +164,18 @@ const APP: () = {
             blue: 0,
         });
 
-        let state = common::State::new(Pattern::Rainbow(Rainbow::default()));
+        let mut adc = Adc::adc1(dp.ADC1, &mut rcc.apb2, clocks);
+
+        let seed = boot_seed(&mut adc);
+
+        adc.release(&mut rcc.apb2);
+
+        // Log the seed so a run can be replayed in the simulator with `--seed`
+        defmt::info!("Seed {:u64}", seed);
+
+        let mut state = common::State::new(Pattern::Rainbow(Rainbow::default())).with_seed(seed);
+
+        state.set_time_scale(TIME_SCALE);
 
         defmt::info!("Config complete");
 
@@ -159,15 +212,10 @@ const APP: () = {
         use stm32f1xx_hal::dma::TransferPayload;
 
         cube.lock(|cube| {
-            for (led_idx, colour) in cube.frame().iter().enumerate() {
+            for (led_idx, colour) in cube.output_iter().enumerate() {
                 let start = led_idx * (8 * 3);
 
-                for (byte_idx, bit) in colour
-                    .divide_by(cube.brightness_divider)
-                    .as_bitbang_data()
-                    .iter()
-                    .enumerate()
-                {
+                for (byte_idx, bit) in colour.as_bitbang_data().iter().enumerate() {
                     unsafe { DATA[start + byte_idx] = *bit }
                 }
             }
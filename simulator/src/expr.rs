@@ -0,0 +1,728 @@
+//! Maths expressions evaluated for every voxel, for prototyping patterns without writing Rust.
+//!
+//! An expression is either `rgb(red, green, blue)`, `hsv(hue, saturation, value)` or a plain
+//! number giving the brightness of a white voxel. Channels run from `0` to `1`, and hue wraps
+//! around every `1`. Expressions can use:
+//!
+//! - The voxel position `x`, `y` and `z` from `0` to `3`, and the time `t` in ms
+//! - The constant `pi`
+//! - `+`, `-`, `*`, `/`, `%` (remainder) and `^` (power), with brackets for grouping
+//! - `sin`, `cos`, `tan`, `abs`, `floor`, `fract`, `sqrt`, `min(a, b)` and `max(a, b)`
+//!
+//! For example `hsv(t/1000 + z/4, 1, sin(x + t/300))`.
+
+use common::{apa106led::Apa106Led, cube::Cube, vm::Inputs, voxel::Voxel};
+use std::{convert::TryInto, fmt};
+
+/// Deepest nesting of brackets, operators and function calls, so a huge expression can't
+/// overflow the stack while it's parsed or evaluated.
+const MAX_DEPTH: usize = 256;
+
+/// Why an expression failed to parse.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ErrorKind {
+    /// Character that can't start any token.
+    UnexpectedCharacter(char),
+
+    /// Digits and decimal points that don't make a number, like `1.2.3`.
+    InvalidNumber(String),
+
+    /// Expression ended part way through.
+    UnexpectedEnd { expected: &'static str },
+
+    /// Token isn't valid at this point.
+    UnexpectedToken {
+        found: String,
+        expected: &'static str,
+    },
+
+    /// Name isn't a known input, constant or function.
+    UnknownName(String),
+
+    /// Function was called with the wrong number of arguments.
+    ArgumentCount {
+        name: &'static str,
+        expected: usize,
+        found: usize,
+    },
+
+    /// `rgb` or `hsv` used anywhere other than around the whole expression.
+    NestedColour(&'static str),
+
+    /// Brackets, operators or function calls nested deeper than [`MAX_DEPTH`].
+    TooDeep,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedCharacter(c) => write!(f, "unexpected character '{}'", c),
+            Self::InvalidNumber(number) => write!(f, "invalid number '{}'", number),
+            Self::UnexpectedEnd { expected } => {
+                write!(f, "expression ended early, expected {}", expected)
+            }
+            Self::UnexpectedToken { found, expected } => {
+                write!(f, "expected {}, found '{}'", expected, found)
+            }
+            Self::UnknownName(name) => write!(
+                f,
+                "unknown name '{}', expected x, y, z, t, pi or a function",
+                name
+            ),
+            Self::ArgumentCount {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{}() takes {} argument{}, found {}",
+                name,
+                expected,
+                if *expected == 1 { "" } else { "s" },
+                found
+            ),
+            Self::NestedColour(name) => {
+                write!(f, "{}() can only be used around the whole expression", name)
+            }
+            Self::TooDeep => write!(
+                f,
+                "expression is nested too deeply, at most {} levels are allowed",
+                MAX_DEPTH
+            ),
+        }
+    }
+}
+
+/// Error from [`Expression::parse`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    /// Byte offset into the source where the error was found.
+    pub position: usize,
+
+    pub kind: ErrorKind,
+}
+
+impl ParseError {
+    /// Format the error under the source it came from, with a marker pointing at the problem.
+    pub fn report(&self, src: &str) -> String {
+        let column = src[0..self.position.min(src.len())].chars().count();
+
+        let width = match &self.kind {
+            ErrorKind::InvalidNumber(number) => number.chars().count(),
+            _ => 1,
+        };
+
+        format!(
+            "{}\n{}{} {}",
+            src,
+            " ".repeat(column),
+            "^".repeat(width),
+            self.kind
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.kind, self.position)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Token<'a> {
+    Number(f32),
+    Name(&'a str),
+    Symbol(char),
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(value) => write!(f, "{}", value),
+            Self::Name(name) => write!(f, "{}", name),
+            Self::Symbol(c) => write!(f, "{}", c),
+        }
+    }
+}
+
+/// Split the source into tokens, each with its byte offset.
+fn tokenise(src: &str) -> Result<Vec<(usize, Token<'_>)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = src.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        let token_end = |pred: fn(char) -> bool| {
+            src[start..]
+                .char_indices()
+                .find(|(_, c)| !pred(*c))
+                .map_or(src.len(), |(offset, _)| start + offset)
+        };
+
+        let (token, end) = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+
+                continue;
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let end = token_end(|c| c.is_ascii_digit() || c == '.');
+
+                let value = src[start..end].parse().map_err(|_| ParseError {
+                    position: start,
+                    kind: ErrorKind::InvalidNumber(src[start..end].to_string()),
+                })?;
+
+                (Token::Number(value), end)
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let end = token_end(|c| c.is_ascii_alphanumeric() || c == '_');
+
+                (Token::Name(&src[start..end]), end)
+            }
+            '+' | '-' | '*' | '/' | '%' | '^' | '(' | ')' | ',' => (Token::Symbol(c), start + 1),
+            c => {
+                return Err(ParseError {
+                    position: start,
+                    kind: ErrorKind::UnexpectedCharacter(c),
+                })
+            }
+        };
+
+        tokens.push((start, token));
+
+        while chars.peek().is_some_and(|(idx, _)| *idx < end) {
+            chars.next();
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Abs,
+    Floor,
+    Fract,
+    Sqrt,
+    Min,
+    Max,
+}
+
+/// Every function with its name and number of arguments.
+const FUNCTIONS: [(&str, Function, usize); 9] = [
+    ("sin", Function::Sin, 1),
+    ("cos", Function::Cos, 1),
+    ("tan", Function::Tan, 1),
+    ("abs", Function::Abs, 1),
+    ("floor", Function::Floor, 1),
+    ("fract", Function::Fract, 1),
+    ("sqrt", Function::Sqrt, 1),
+    ("min", Function::Min, 2),
+    ("max", Function::Max, 2),
+];
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Number(f32),
+    X,
+    Y,
+    Z,
+    T,
+    Neg(Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+impl Node {
+    fn eval(&self, inputs: &Inputs) -> f32 {
+        match self {
+            Self::Number(value) => *value,
+            Self::X => inputs.x,
+            Self::Y => inputs.y,
+            Self::Z => inputs.z,
+            Self::T => inputs.t,
+            Self::Neg(node) => -node.eval(inputs),
+            Self::Binary(op, a, b) => {
+                let a = a.eval(inputs);
+                let b = b.eval(inputs);
+
+                match op {
+                    BinaryOp::Add => a + b,
+                    BinaryOp::Sub => a - b,
+                    BinaryOp::Mul => a * b,
+                    BinaryOp::Div => a / b,
+                    BinaryOp::Rem => a.rem_euclid(b),
+                    BinaryOp::Pow => a.powf(b),
+                }
+            }
+            Self::Call(function, args) => {
+                let arg = |idx: usize| args[idx].eval(inputs);
+
+                match function {
+                    Function::Sin => arg(0).sin(),
+                    Function::Cos => arg(0).cos(),
+                    Function::Tan => arg(0).tan(),
+                    Function::Abs => arg(0).abs(),
+                    Function::Floor => arg(0).floor(),
+                    Function::Fract => arg(0).fract(),
+                    Function::Sqrt => arg(0).sqrt(),
+                    Function::Min => arg(0).min(arg(1)),
+                    Function::Max => arg(0).max(arg(1)),
+                }
+            }
+        }
+    }
+}
+
+/// Recursive descent parser over a list of tokens.
+struct Parser<'a> {
+    tokens: Vec<(usize, Token<'a>)>,
+    next: usize,
+
+    /// Length of the source, used as the position of errors at the end of the expression.
+    end: usize,
+
+    /// Current nesting depth of the expression being parsed.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.next).map(|(_, token)| *token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.next)
+            .map_or(self.end, |(position, _)| *position)
+    }
+
+    fn error(&self, expected: &'static str) -> ParseError {
+        let kind = match self.peek() {
+            Some(token) => ErrorKind::UnexpectedToken {
+                found: token.to_string(),
+                expected,
+            },
+            None => ErrorKind::UnexpectedEnd { expected },
+        };
+
+        ParseError {
+            position: self.position(),
+            kind,
+        }
+    }
+
+    /// Consume the next token if it's the given symbol.
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(Token::Symbol(symbol)) {
+            self.next += 1;
+
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: char, expected: &'static str) -> Result<(), ParseError> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(self.error(expected))
+        }
+    }
+
+    /// Go one level deeper into the expression, failing if it's nested too deeply.
+    fn descend(&mut self) -> Result<(), ParseError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(ParseError {
+                position: self.position(),
+                kind: ErrorKind::TooDeep,
+            });
+        }
+
+        self.depth += 1;
+
+        Ok(())
+    }
+
+    /// `sum = product (("+" | "-") product)*`
+    fn sum(&mut self) -> Result<Node, ParseError> {
+        let depth = self.depth;
+        let mut node = self.product()?;
+
+        loop {
+            let op = if self.eat('+') {
+                BinaryOp::Add
+            } else if self.eat('-') {
+                BinaryOp::Sub
+            } else {
+                self.depth = depth;
+
+                return Ok(node);
+            };
+
+            // Each operator nests the expression so far one level deeper
+            self.descend()?;
+
+            node = Node::Binary(op, Box::new(node), Box::new(self.product()?));
+        }
+    }
+
+    /// `product = unary (("*" | "/" | "%") unary)*`
+    fn product(&mut self) -> Result<Node, ParseError> {
+        let depth = self.depth;
+        let mut node = self.unary()?;
+
+        loop {
+            let op = if self.eat('*') {
+                BinaryOp::Mul
+            } else if self.eat('/') {
+                BinaryOp::Div
+            } else if self.eat('%') {
+                BinaryOp::Rem
+            } else {
+                self.depth = depth;
+
+                return Ok(node);
+            };
+
+            self.descend()?;
+
+            node = Node::Binary(op, Box::new(node), Box::new(self.unary()?));
+        }
+    }
+
+    /// `unary = "-" unary | power`
+    fn unary(&mut self) -> Result<Node, ParseError> {
+        // Every bracket, function call and power goes back through here
+        self.descend()?;
+
+        let node = if self.eat('-') {
+            Node::Neg(Box::new(self.unary()?))
+        } else {
+            self.power()?
+        };
+
+        self.depth -= 1;
+
+        Ok(node)
+    }
+
+    /// `power = atom ("^" unary)?`, so `-2^2` is `-4` and `2^3^2` is `2^9`.
+    fn power(&mut self) -> Result<Node, ParseError> {
+        let base = self.atom()?;
+
+        if self.eat('^') {
+            Ok(Node::Binary(
+                BinaryOp::Pow,
+                Box::new(base),
+                Box::new(self.unary()?),
+            ))
+        } else {
+            Ok(base)
+        }
+    }
+
+    /// `atom = number | name | name "(" arguments ")" | "(" sum ")"`
+    fn atom(&mut self) -> Result<Node, ParseError> {
+        let position = self.position();
+
+        match self.peek() {
+            Some(Token::Number(value)) => {
+                self.next += 1;
+
+                Ok(Node::Number(value))
+            }
+            Some(Token::Symbol('(')) => {
+                self.next += 1;
+
+                let node = self.sum()?;
+
+                self.expect(')', "')'")?;
+
+                Ok(node)
+            }
+            Some(Token::Name(name)) => {
+                self.next += 1;
+
+                let node = match name {
+                    "x" => Node::X,
+                    "y" => Node::Y,
+                    "z" => Node::Z,
+                    "t" => Node::T,
+                    "pi" => Node::Number(core::f32::consts::PI),
+                    "rgb" | "hsv" => {
+                        let name = if name == "rgb" { "rgb" } else { "hsv" };
+
+                        return Err(ParseError {
+                            position,
+                            kind: ErrorKind::NestedColour(name),
+                        });
+                    }
+                    _ => {
+                        let (name, function, arity) = FUNCTIONS
+                            .iter()
+                            .find(|(function, _, _)| *function == name)
+                            .copied()
+                            .ok_or_else(|| ParseError {
+                                position,
+                                kind: ErrorKind::UnknownName(name.to_string()),
+                            })?;
+
+                        Node::Call(function, self.arguments(name, arity, position)?)
+                    }
+                };
+
+                Ok(node)
+            }
+            _ => Err(self.error("a number, name or '('")),
+        }
+    }
+
+    /// Parse a bracketed, comma separated argument list and check it has `arity` arguments.
+    fn arguments(
+        &mut self,
+        name: &'static str,
+        arity: usize,
+        position: usize,
+    ) -> Result<Vec<Node>, ParseError> {
+        self.expect('(', "'(' after a function name")?;
+
+        let mut args = vec![self.sum()?];
+
+        while self.eat(',') {
+            args.push(self.sum()?);
+        }
+
+        self.expect(')', "',' or ')'")?;
+
+        if args.len() != arity {
+            return Err(ParseError {
+                position,
+                kind: ErrorKind::ArgumentCount {
+                    name,
+                    expected: arity,
+                    found: args.len(),
+                },
+            });
+        }
+
+        Ok(args)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Output {
+    Rgb([Node; 3]),
+    Hsv([Node; 3]),
+    Brightness(Node),
+}
+
+/// A parsed expression that gives the colour of each voxel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expression {
+    output: Output,
+}
+
+impl Expression {
+    pub fn parse(src: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            tokens: tokenise(src)?,
+            next: 0,
+            end: src.len(),
+            depth: 0,
+        };
+
+        let colour = match (parser.peek(), parser.tokens.get(1).map(|(_, t)| *t)) {
+            (Some(Token::Name("rgb")), Some(Token::Symbol('('))) => Some("rgb"),
+            (Some(Token::Name("hsv")), Some(Token::Symbol('('))) => Some("hsv"),
+            _ => None,
+        };
+
+        let output = match colour {
+            Some(name) => {
+                let position = parser.position();
+                parser.next += 1;
+
+                let [a, b, c]: [Node; 3] = parser
+                    .arguments(name, 3, position)?
+                    .try_into()
+                    .expect("Argument count already checked");
+
+                if name == "rgb" {
+                    Output::Rgb([a, b, c])
+                } else {
+                    Output::Hsv([a, b, c])
+                }
+            }
+            None => Output::Brightness(parser.sum()?),
+        };
+
+        if parser.peek().is_some() {
+            return Err(parser.error("an operator or the end of the expression"));
+        }
+
+        Ok(Self { output })
+    }
+
+    /// Colour of the voxel at `idx` at `time` ms.
+    pub fn colour_at(&self, idx: usize, time: u32) -> Apa106Led {
+        let voxel = Voxel::from_index_unchecked(idx);
+
+        let inputs = Inputs {
+            x: voxel.x as f32,
+            y: voxel.y as f32,
+            z: voxel.z as f32,
+            t: time as f32,
+        };
+
+        let channel = |node: &Node| (node.eval(&inputs).clamp(0.0, 1.0) * 255.0) as u8;
+
+        match &self.output {
+            Output::Rgb([red, green, blue]) => Apa106Led {
+                red: channel(red),
+                green: channel(green),
+                blue: channel(blue),
+            },
+            Output::Hsv([hue, saturation, value]) => Apa106Led::from_hsv(
+                hue.eval(&inputs),
+                saturation.eval(&inputs),
+                value.eval(&inputs),
+            ),
+            Output::Brightness(node) => {
+                let level = channel(node);
+
+                Apa106Led {
+                    red: level,
+                    green: level,
+                    blue: level,
+                }
+            }
+        }
+    }
+
+    /// Draw every voxel into the cube.
+    pub fn render(&self, time: u32, cube: &mut Cube) {
+        cube.fill_iter((0..64).map(|idx| self.colour_at(idx, time)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(src: &str, x: f32, t: f32) -> f32 {
+        let expression = Expression::parse(src).unwrap();
+
+        match expression.output {
+            Output::Brightness(node) => node.eval(&Inputs {
+                x,
+                y: 0.0,
+                z: 0.0,
+                t,
+            }),
+            _ => panic!("Not a brightness expression"),
+        }
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("1 + 2 * 3", 0.0, 0.0), 7.0);
+        assert_eq!(eval("(1 + 2) * 3", 0.0, 0.0), 9.0);
+        assert_eq!(eval("-2^2", 0.0, 0.0), -4.0);
+        assert_eq!(eval("2^3^2", 0.0, 0.0), 512.0);
+        assert_eq!(eval("10 - 4 - 3", 0.0, 0.0), 3.0);
+        assert_eq!(eval("-1 % 4", 0.0, 0.0), 3.0);
+        assert_eq!(eval("max(x, t / 1000) + floor(2.5)", 3.0, 5000.0), 7.0);
+    }
+
+    #[test]
+    fn colours() {
+        let corner = Voxel { x: 3, y: 0, z: 0 }.into_index_unchecked();
+
+        assert_eq!(
+            Expression::parse("rgb(x / 3, 0, 2)")
+                .unwrap()
+                .colour_at(corner, 0),
+            Apa106Led {
+                red: 255,
+                green: 0,
+                blue: 255
+            }
+        );
+        assert_eq!(
+            Expression::parse("hsv(t / 1000 + z / 4, 1, sin(x + t / 300))")
+                .unwrap()
+                .colour_at(0, 0),
+            Apa106Led::OFF
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |src: &str| Expression::parse(src).unwrap_err();
+
+        assert_eq!(
+            error("sin(x) + q"),
+            ParseError {
+                position: 9,
+                kind: ErrorKind::UnknownName("q".to_string())
+            }
+        );
+        assert_eq!(
+            error("1 + hsv(1, 1, 1)").kind,
+            ErrorKind::NestedColour("hsv")
+        );
+        assert_eq!(
+            error("min(1)").kind,
+            ErrorKind::ArgumentCount {
+                name: "min",
+                expected: 2,
+                found: 1
+            }
+        );
+        assert_eq!(
+            error("(x + 1").kind,
+            ErrorKind::UnexpectedEnd { expected: "')'" }
+        );
+        assert_eq!(error("x $ 1").kind, ErrorKind::UnexpectedCharacter('$'));
+        assert_eq!(
+            error("x + 1.2.3"),
+            ParseError {
+                position: 4,
+                kind: ErrorKind::InvalidNumber("1.2.3".to_string())
+            }
+        );
+        assert_eq!(
+            error(&format!("{}x", "(".repeat(100_000))).kind,
+            ErrorKind::TooDeep
+        );
+        assert_eq!(error(&"-".repeat(100_000)).kind, ErrorKind::TooDeep);
+        assert_eq!(error(&"x+".repeat(100_000)).kind, ErrorKind::TooDeep);
+        assert_eq!(error("x 1").position, 2);
+
+        assert_eq!(
+            error("sin(x) + q").report("sin(x) + q"),
+            "sin(x) + q\n         ^ unknown name 'q', expected x, y, z, t, pi or a function"
+        );
+        assert_eq!(
+            error("1.2.3 * x").report("1.2.3 * x"),
+            "1.2.3 * x\n^^^^^ invalid number '1.2.3'"
+        );
+    }
+}
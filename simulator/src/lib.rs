@@ -0,0 +1,391 @@
+use common::{
+    calibration::{Calibration, CALIBRATION_LEN},
+    clock::Clock,
+    cube::Cube,
+    input::InputEvent,
+    patterns::{registry, Pattern, Scripted, MAX_PROGRAM_LEN},
+    state::State,
+    vm,
+};
+use std::{convert::TryInto, fs, process};
+
+pub mod expr;
+
+pub use expr::Expression;
+
+/// What to draw, as chosen on the command line.
+enum Source {
+    /// Pattern name from the registry.
+    Pattern(String),
+
+    /// Source of an [`Expression`].
+    Expression(String),
+
+    /// Path to a [`vm`] program, run with the [`Scripted`] pattern.
+    Script(String),
+}
+
+struct Args {
+    source: Source,
+
+    /// Seed for randomised patterns, to replay the same run.
+    seed: Option<u64>,
+
+    /// Global brightness applied when the cube is drawn.
+    brightness: u8,
+
+    /// Per-LED calibration applied when the cube is drawn.
+    calibration: Calibration,
+}
+
+/// Parse the command line.
+///
+/// `--pattern <name>` picks a pattern, falling back to `default`. `--expr <expression>` draws an
+/// [`Expression`] instead, and `--script <file>` runs a [`vm`] program. `--seed <number>` seeds randomised patterns. `--brightness <0-255>` and
+/// `--calibration <file>` set what's applied to the output, like on the real cube. `--list-patterns`
+/// prints every available pattern and exits.
+fn parse_args(default: &str) -> Args {
+    let mut args = std::env::args().skip(1);
+    let mut source = Source::Pattern(default.to_string());
+    let mut seed = None;
+    let mut brightness = u8::MAX;
+    let mut calibration = Calibration::IDENTITY;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--pattern" | "-p" => match args.next() {
+                Some(value) => source = Source::Pattern(value),
+                None => {
+                    eprintln!("--pattern requires a pattern name");
+                    process::exit(1);
+                }
+            },
+            "--expr" | "-e" => match args.next() {
+                Some(value) => source = Source::Expression(value),
+                None => {
+                    eprintln!("--expr requires an expression, e.g. --expr 'hsv(t / 1000, 1, 1)'");
+                    process::exit(1);
+                }
+            },
+            "--script" => match args.next() {
+                Some(path) => source = Source::Script(path),
+                None => {
+                    eprintln!("--script requires the path to a VM program");
+                    process::exit(1);
+                }
+            },
+            "--seed" | "-s" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => seed = Some(value),
+                None => {
+                    eprintln!("--seed requires a number");
+                    process::exit(1);
+                }
+            },
+            "--brightness" | "-b" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) => brightness = value,
+                None => {
+                    eprintln!("--brightness requires a number from 0 to 255");
+                    process::exit(1);
+                }
+            },
+            "--calibration" => match args.next() {
+                Some(path) => calibration = load_calibration(&path),
+                None => {
+                    eprintln!("--calibration requires a file generated by Calibration::to_bytes");
+                    process::exit(1);
+                }
+            },
+            "--list-patterns" => {
+                list_patterns();
+                process::exit(0);
+            }
+            other => {
+                eprintln!("Unrecognised argument {}", other);
+                process::exit(1);
+            }
+        }
+    }
+
+    Args {
+        source,
+        seed,
+        brightness,
+        calibration,
+    }
+}
+
+/// Assemble a [`vm`] program from a file and load it into a [`Scripted`] pattern.
+fn load_script(path: &str) -> Scripted {
+    let src = fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("Failed to read script {}: {}", path, e);
+        process::exit(1);
+    });
+
+    let mut code = [0; MAX_PROGRAM_LEN];
+
+    let len = vm::assemble(&src, &mut code).unwrap_or_else(|e| {
+        eprintln!("Failed to assemble {}, {}", path, e);
+        process::exit(1);
+    });
+
+    Scripted::default()
+        .with_program(&code[0..len])
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to run {}: {}", path, e);
+            process::exit(1);
+        })
+}
+
+/// Read a calibration table saved with [`Calibration::to_bytes`].
+fn load_calibration(path: &str) -> Calibration {
+    let bytes = fs::read(path).unwrap_or_else(|e| {
+        eprintln!("Failed to read calibration file {}: {}", path, e);
+        process::exit(1);
+    });
+
+    match bytes.as_slice().try_into() {
+        Ok(bytes) => Calibration::from_bytes(bytes),
+        Err(_) => {
+            eprintln!(
+                "Calibration file {} is {} bytes long, expected {}",
+                path,
+                bytes.len(),
+                CALIBRATION_LEN
+            );
+            process::exit(1);
+        }
+    }
+}
+
+fn build_pattern(name: &str) -> Pattern {
+    match registry::by_name(name) {
+        Some(entry) => entry.build(),
+        None => {
+            eprintln!("Unknown pattern {}. Available patterns:", name);
+            list_patterns();
+            process::exit(1);
+        }
+    }
+}
+
+fn list_patterns() {
+    for entry in registry::iter() {
+        println!("{:>3} {}", entry.id, entry.name);
+    }
+}
+
+/// Time advanced by [`Control::Step`], ms. One frame at the firmware's 30 FPS.
+const STEP_TIME: u32 = 1000 / 30;
+
+/// Playback controls shared by both simulators.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Control {
+    /// Pause or resume playback.
+    TogglePause,
+
+    /// Double the playback speed.
+    Faster,
+
+    /// Halve the playback speed.
+    Slower,
+
+    /// Swap between playing forwards and backwards.
+    Reverse,
+
+    /// Go back to playing forwards at normal speed.
+    NormalSpeed,
+
+    /// Advance by a single frame.
+    Step,
+}
+
+/// Amount [`Command::Brighter`] and [`Command::Dimmer`] change the global brightness by.
+const BRIGHTNESS_STEP: u8 = 16;
+
+/// Something asked for from the keyboard or mouse.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Command {
+    Control(Control),
+
+    /// Input event passed on to the active pattern.
+    Input(InputEvent),
+
+    /// Raise the global brightness by one step.
+    Brighter,
+
+    /// Lower the global brightness by one step.
+    Dimmer,
+}
+
+/// What draws into the cube.
+enum Playback {
+    /// Patterns played by [`State`], starting from the one picked with `--pattern`.
+    State(Box<State>),
+
+    /// Expression from `--expr`, drawn directly without the playlist.
+    Expression {
+        expression: Expression,
+        clock: Clock,
+    },
+}
+
+/// Draws either the pattern playlist or a single expression into the cube.
+pub struct Runner {
+    playback: Playback,
+
+    /// Cube drawn into. Its brightness and calibration are applied by [`Cube::output_iter`], so
+    /// the simulators show what the real cube would.
+    cube: Cube,
+}
+
+impl Runner {
+    /// Create a runner from the command line.
+    ///
+    /// `--pattern <name>` picks the starting pattern, falling back to `default`, and `--seed
+    /// <number>` seeds randomised patterns. `--expr <expression>` draws an [`Expression`] instead,
+    /// and `--script <file>` assembles and runs a [`vm`] program.
+    /// `--brightness <0-255>` and `--calibration <file>` set up the cube's output.
+    ///
+    /// `--list-patterns` prints every available pattern and exits. An unknown pattern, invalid
+    /// expression or script, or unreadable calibration file prints what went wrong and exits with
+    /// an error.
+    pub fn from_args(default: &str) -> Self {
+        let args = parse_args(default);
+
+        let playback = match args.source {
+            Source::Pattern(name) => {
+                let state = State::new(build_pattern(&name));
+
+                Playback::State(Box::new(match args.seed {
+                    Some(seed) => state.with_seed(seed),
+                    None => state,
+                }))
+            }
+            Source::Script(path) => {
+                Playback::State(Box::new(State::new(Pattern::Scripted(load_script(&path)))))
+            }
+            Source::Expression(src) => match Expression::parse(&src) {
+                Ok(expression) => Playback::Expression {
+                    expression,
+                    clock: Clock::default(),
+                },
+                Err(e) => {
+                    eprintln!("Invalid expression:\n\n{}", e.report(&src));
+                    process::exit(1);
+                }
+            },
+        };
+
+        let mut cube = Cube::new(args.brightness);
+
+        cube.calibration = args.calibration;
+
+        Self { playback, cube }
+    }
+
+    /// Draw the next frame into the cube.
+    pub fn drive(&mut self, wall_time: u32) {
+        let cube = &mut self.cube;
+
+        match &mut self.playback {
+            Playback::State(state) => state.drive(wall_time, cube),
+            Playback::Expression { expression, clock } => {
+                expression.render(clock.tick(wall_time, 0), cube)
+            }
+        }
+    }
+
+    /// Cube holding the last drawn frame. Draw it with [`Cube::output_iter`].
+    pub fn cube(&self) -> &Cube {
+        &self.cube
+    }
+
+    pub fn clock(&self) -> &Clock {
+        match &self.playback {
+            Playback::State(state) => state.clock(),
+            Playback::Expression { clock, .. } => clock,
+        }
+    }
+
+    pub fn command(&mut self, command: Command) {
+        match command {
+            Command::Control(control) => self.control(control),
+            Command::Input(event) => self.input(event),
+            Command::Brighter => {
+                self.set_brightness(self.cube.brightness.saturating_add(BRIGHTNESS_STEP))
+            }
+            Command::Dimmer => {
+                self.set_brightness(self.cube.brightness.saturating_sub(BRIGHTNESS_STEP))
+            }
+        }
+    }
+
+    /// Set the global brightness applied to the output and print it.
+    pub fn set_brightness(&mut self, brightness: u8) {
+        self.cube.brightness = brightness;
+
+        println!("Brightness {}", brightness);
+    }
+
+    /// Pass an input event to the active pattern. Expressions ignore input.
+    pub fn input(&mut self, event: InputEvent) {
+        if let Playback::State(state) = &mut self.playback {
+            state.input(event);
+        }
+    }
+
+    /// Use the mouse pointer position as axes 0 and 1. `x` and `y` are fractions of the window
+    /// size from the top left corner.
+    pub fn pointer(&mut self, x: f32, y: f32) {
+        self.input(InputEvent::Axis {
+            axis: 0,
+            value: (x * 2.0 - 1.0).clamp(-1.0, 1.0),
+        });
+        self.input(InputEvent::Axis {
+            axis: 1,
+            value: (1.0 - y * 2.0).clamp(-1.0, 1.0),
+        });
+    }
+
+    /// Centre axes 0 and 1 once the mouse pointer stops controlling them.
+    pub fn release_pointer(&mut self) {
+        for axis in 0..2 {
+            self.input(InputEvent::Axis { axis, value: 0.0 });
+        }
+    }
+
+    /// Playback clock, and the earliest time it can be stepped back to.
+    fn clock_mut(&mut self) -> (&mut Clock, u32) {
+        match &mut self.playback {
+            Playback::State(state) => {
+                let floor = state.earliest_time();
+
+                (state.clock_mut(), floor)
+            }
+            Playback::Expression { clock, .. } => (clock, 0),
+        }
+    }
+
+    /// Apply a playback control and print the new playback speed.
+    pub fn control(&mut self, control: Control) {
+        let (clock, floor) = self.clock_mut();
+        let time_scale = clock.time_scale();
+
+        match control {
+            Control::TogglePause if clock.is_paused() => clock.resume(),
+            Control::TogglePause => clock.pause(),
+            Control::Faster => clock.set_time_scale(time_scale * 2.0),
+            Control::Slower => clock.set_time_scale(time_scale / 2.0),
+            Control::Reverse => clock.set_time_scale(-time_scale),
+            Control::NormalSpeed => clock.set_time_scale(1.0),
+            Control::Step => clock.step(STEP_TIME, floor),
+        }
+
+        println!(
+            "{}x{} at {} ms",
+            clock.time_scale(),
+            if clock.is_paused() { " (paused)" } else { "" },
+            clock.time()
+        );
+    }
+}
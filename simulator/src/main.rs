@@ -1,12 +1,16 @@
-use common::{apa106led::Apa106Led, cube::Cube, patterns::*, state::State, voxel::Voxel};
+use common::{input::InputEvent, voxel::Voxel};
 use core::f32::consts::PI;
 use kiss3d::camera::ArcBall;
+use kiss3d::event::{Action, Key, Modifiers, WindowEvent};
 use kiss3d::light::Light;
-use kiss3d::window::Window;
 use kiss3d::nalgebra::{Point3, Translation3, UnitQuaternion, Vector3};
+use kiss3d::window::Window;
+use simulator::{Command, Control, Runner};
 use std::time::Instant;
 
 fn main() {
+    let mut runner = Runner::from_args("rainbow");
+
     let eye = Point3::new(10.0f32, 10.0, 10.0);
     let at = Point3::origin();
     let mut arc_ball = ArcBall::new(eye, at);
@@ -20,8 +24,6 @@ fn main() {
     floor.set_color(0.2, 0.2, 0.2);
     floor.append_rotation_wrt_center(&align_z_up);
 
-    let mut cube = Cube::new(1);
-
     let mut voxels = Vec::new();
 
     let sphere_scale = 0.35;
@@ -39,7 +41,7 @@ fn main() {
     g.set_local_scale(sphere_scale, sphere_scale, sphere_scale);
 
     for idx in 0..64 {
-        let pos = Voxel::from_index(idx);
+        let pos = Voxel::from_index_unchecked(idx);
 
         let x = pos.x as f32;
         let y = pos.y as f32;
@@ -49,9 +51,9 @@ fn main() {
 
         // NOTE: Weird ordering here as Z faces out of screen with KISS3D
         s.append_translation(&Translation3::new(
-            x as f32 * cube_scale,
-            -z as f32 * cube_scale,
-            -y as f32 * cube_scale,
+            x * cube_scale,
+            -z * cube_scale,
+            -y * cube_scale,
         ));
 
         voxels.push(s);
@@ -59,17 +61,73 @@ fn main() {
 
     let start = Instant::now();
 
-    let mut state = State::new(Pattern::Rainbow(Rainbow::default()));
-
-    cube.set_at_coord(Voxel { x: 0, y: 0, z: 0 }, Apa106Led::WARM_WHITE);
+    // Whether the mouse pointer is steering axes 0 and 1, while shift is held
+    let mut pointing = false;
 
     while window.render_with_camera(&mut arc_ball) {
-        let time = start.elapsed().as_millis();
+        let (width, height) = (window.width() as f32, window.height() as f32);
+
+        for event in window.events().iter() {
+            let command = match event.value {
+                WindowEvent::Key(key, Action::Press, _) => match key {
+                    Key::Space => Some(Command::Control(Control::TogglePause)),
+                    Key::Up => Some(Command::Control(Control::Faster)),
+                    Key::Down => Some(Command::Control(Control::Slower)),
+                    Key::R => Some(Command::Control(Control::Reverse)),
+                    Key::N => Some(Command::Control(Control::NormalSpeed)),
+                    Key::Right => Some(Command::Control(Control::Step)),
+                    Key::Equals => Some(Command::Brighter),
+                    Key::Minus => Some(Command::Dimmer),
+                    Key::Z => Some(Command::Input(InputEvent::ButtonDown(0))),
+                    Key::X => Some(Command::Input(InputEvent::ButtonDown(1))),
+                    Key::Q => Some(Command::Input(InputEvent::Encoder {
+                        encoder: 0,
+                        steps: -1,
+                    })),
+                    Key::E => Some(Command::Input(InputEvent::Encoder {
+                        encoder: 0,
+                        steps: 1,
+                    })),
+                    _ => None,
+                },
+                WindowEvent::Key(key, Action::Release, _) => match key {
+                    Key::Z => Some(Command::Input(InputEvent::ButtonUp(0))),
+                    Key::X => Some(Command::Input(InputEvent::ButtonUp(1))),
+                    Key::LShift | Key::RShift if pointing => {
+                        pointing = false;
+                        runner.release_pointer();
+
+                        None
+                    }
+                    _ => None,
+                },
+                // Plain mouse movement is left to the camera
+                WindowEvent::CursorPos(x, y, modifiers) if modifiers.contains(Modifiers::Shift) => {
+                    pointing = true;
+                    runner.pointer(x as f32 / width, y as f32 / height);
+
+                    None
+                }
+                WindowEvent::CursorPos(..) | WindowEvent::CursorEnter(false) if pointing => {
+                    pointing = false;
+                    runner.release_pointer();
+
+                    None
+                }
+                _ => None,
+            };
+
+            if let Some(command) = command {
+                runner.command(command);
+            }
+        }
+
+        let time = start.elapsed().as_millis() as u32;
 
-        state.drive(time as u32, &mut cube);
+        runner.drive(time);
 
         // Update voxel colours
-        for (sphere, c) in voxels.iter_mut().zip(cube.frame().iter()) {
+        for (sphere, c) in voxels.iter_mut().zip(runner.cube().output_iter()) {
             sphere.set_color(
                 c.red as f32 / 255.0,
                 c.green as f32 / 255.0,